@@ -0,0 +1,105 @@
+use crate::commands::complete::{Action, BuiltinSpec};
+use crate::helpers::expand::shell_quote;
+use std::collections::BTreeMap;
+
+pub const ALIAS_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-p"],
+    actions: &[Action::Alias],
+};
+
+pub const UNALIAS_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-a"],
+    actions: &[Action::Alias],
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    entries: BTreeMap<String, String>,
+}
+
+impl Aliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(String::as_str)
+    }
+
+    pub fn set(&mut self, name: String, value: String) {
+        self.entries.insert(name, value);
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.entries.iter()
+    }
+}
+
+pub fn format_alias(name: &str, value: &str) -> String {
+    format!("alias {}={}", name, shell_quote(value))
+}
+
+fn is_valid_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || "/$`=\\'\"|&;<>()".contains(c))
+}
+
+pub fn alias(args: Vec<String>, aliases: &mut Aliases) -> bool {
+    let args: Vec<String> = args.into_iter().filter(|a| a != "-p").collect();
+    if args.is_empty() {
+        for (name, value) in aliases.iter() {
+            println!("{}", format_alias(name, value));
+        }
+        return true;
+    }
+
+    let mut ok = true;
+    for arg in args {
+        match arg.split_once('=') {
+            Some((name, value)) => {
+                if !is_valid_alias_name(name) {
+                    eprintln!("alias: `{}': invalid alias name", name);
+                    ok = false;
+                    continue;
+                }
+                aliases.set(name.to_string(), value.to_string());
+            }
+            None => match aliases.get(&arg) {
+                Some(value) => println!("{}", format_alias(&arg, value)),
+                None => {
+                    eprintln!("alias: {}: not found", arg);
+                    ok = false;
+                }
+            },
+        }
+    }
+    ok
+}
+
+pub fn unalias(args: Vec<String>, aliases: &mut Aliases) -> bool {
+    if args.is_empty() {
+        eprintln!("unalias: usage: unalias [-a] name [name ...]");
+        return false;
+    }
+
+    let mut ok = true;
+    for arg in args {
+        if arg == "-a" {
+            aliases.clear();
+        } else if !aliases.remove(&arg) {
+            eprintln!("unalias: {}: not found", arg);
+            ok = false;
+        }
+    }
+    ok
+}
@@ -1,18 +1,29 @@
+use crate::commands::complete::{Action, BuiltinSpec};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     terminal::enable_raw_mode,
 };
 use std::{
     fs::File,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
     path::Path,
 };
 
+pub const CAT_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[Action::File],
+};
+
 pub fn cat(args: Vec<String>) -> bool {
     let mut ctr = 0;
 
     let stdout = io::stdout();
-    if args.is_empty() {
+    if args.is_empty() && !io::stdin().is_terminal() {
+        if let Err(e) = io::copy(&mut io::stdin().lock(), &mut stdout.lock()) {
+            eprintln!("cat: -: {}", e);
+            return false;
+        }
+    } else if args.is_empty() {
         match enable_raw_mode() {
             Ok(_) => (),
             Err(e) => {
@@ -24,43 +35,41 @@ pub fn cat(args: Vec<String>) -> bool {
         let mut input_buffer = String::new();
 
         loop {
-            if let Event::Key(key_event) = event::read().unwrap() {
-                if key_event.kind == KeyEventKind::Press {
-                    match key_event.code {
-                        KeyCode::Char(c) if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
-                            if c == 'd' {
-                                print!("\r\n");
-                                break;
-                            } else if c == 'c' {
-                                print!("^C\r\n");
-                                break;
-                            }
-                            io::stdout().flush().ok();
-                        }
-
-                        KeyCode::Char(c) => {
-                            print!("{}", c);
-                            input_buffer.push(c);
-                            io::stdout().flush().ok();
+            if let Event::Key(key_event) = event::read().unwrap()
+                && key_event.kind == KeyEventKind::Press
+            {
+                match key_event.code {
+                    KeyCode::Char(c) if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        if c == 'd' {
+                            print!("\r\n");
+                            break;
+                        } else if c == 'c' {
+                            print!("^C\r\n");
+                            break;
                         }
+                        io::stdout().flush().ok();
+                    }
 
-                        KeyCode::Backspace => {
-                            if !input_buffer.is_empty() {
-                                input_buffer.pop();
-                                print!("\x08 \x08");
-                                io::stdout().flush().ok();
-                            }
-                        }
+                    KeyCode::Char(c) => {
+                        print!("{}", c);
+                        input_buffer.push(c);
+                        io::stdout().flush().ok();
+                    }
 
-                        KeyCode::Enter => {
-                            print!("\r\n");
-                            print!("{}\r\n", input_buffer);
-                            io::stdout().flush().ok();
-                            input_buffer.clear();
-                        }
+                    KeyCode::Backspace if !input_buffer.is_empty() => {
+                        input_buffer.pop();
+                        print!("\x08 \x08");
+                        io::stdout().flush().ok();
+                    }
 
-                        _ => {}
+                    KeyCode::Enter => {
+                        print!("\r\n");
+                        print!("{}\r\n", input_buffer);
+                        io::stdout().flush().ok();
+                        input_buffer.clear();
                     }
+
+                    _ => {}
                 }
             }
         }
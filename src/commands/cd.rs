@@ -1,11 +1,43 @@
-use crate::commands::pwd_state::PwdState;
-use std::{env, io::ErrorKind, path::PathBuf};
+use crate::commands::complete::{Action, BuiltinSpec};
+use crate::commands::pwd_state::{PwdState, logical_path};
+use crate::commands::z::record;
+use crate::helpers::completion::expand_tilde;
+use std::{
+    env,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+pub const CD_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-L", "-P"],
+    actions: &[Action::Directory],
+};
 
 pub fn command_cd(
-    error_path: Vec<String>,
+    mut error_path: Vec<String>,
     mut args: Vec<String>,
     pwd_state: &mut PwdState,
 ) -> bool {
+    // -L (the default) follows `..` through the path as typed, -P through the real one.
+    let mut physical = false;
+    while let Some(flags) = args
+        .first()
+        .and_then(|arg| arg.strip_prefix('-'))
+        .filter(|flags| !flags.is_empty() && flags.chars().all(|c| c == 'L' || c == 'P'))
+    {
+        physical = flags.ends_with('P');
+        args.remove(0);
+        if !error_path.is_empty() {
+            error_path.remove(0);
+        }
+    }
+    if args.first().is_some_and(|arg| arg == "--") {
+        args.remove(0);
+        if !error_path.is_empty() {
+            error_path.remove(0);
+        }
+    }
+
     if args.len() > 1 {
         eprintln!("cd: too many arguments");
         return false;
@@ -23,52 +55,98 @@ pub fn command_cd(
         }
     } else if args[0] == "-" {
         PathBuf::from(pwd_state.get_old_dir())
-    } else if args[0] == "~" {
-        match env::var("HOME") {
-            Ok(path) => PathBuf::from(path),
-            Err(_) => {
-                eprintln!("cd: HOME environment variable not set");
-                return false;
-            }
-        }
     } else {
         PathBuf::from(&args[0])
     };
+    let (target_dir, found_in_cdpath) = args
+        .first()
+        .and_then(|dir| search_cdpath(dir, pwd_state))
+        .unwrap_or((target_dir, false));
 
+    let shown = error_path
+        .first()
+        .cloned()
+        .unwrap_or_else(|| target_dir.display().to_string());
+    if !change_dir("cd", &target_dir, &shown, physical, pwd_state) {
+        return false;
+    }
+    if found_in_cdpath || (!args.is_empty() && args[0] == "-") {
+        println!("{}", pwd_state.get_current_dir());
+    }
+    true
+}
+
+// Relative names not starting with `.` or `..` are looked up under each CDPATH entry,
+// where an empty entry is the current directory. Finding one through a named entry is
+// worth announcing, since it may not be where the user expected.
+fn search_cdpath(dir: &str, pwd_state: &PwdState) -> Option<(PathBuf, bool)> {
+    let first = Path::new(dir).components().next();
+    if dir == "-" || !matches!(first, Some(Component::Normal(_))) {
+        return None;
+    }
+    let cdpath = env::var("CDPATH").ok()?;
+    cdpath.split(':').find_map(|entry| {
+        let base = if entry.is_empty() {
+            PathBuf::from(".")
+        } else {
+            expand_tilde(entry)
+        };
+        let candidate = logical_path(&pwd_state.get_current_dir(), &base.join(dir));
+        candidate.is_dir().then_some((candidate, !entry.is_empty()))
+    })
+}
+
+// Moves the shell into `target_dir` and records where it came from, reporting failures
+// under `name` with the path as the user typed it. A logical move that fails, such as
+// one through a directory deleted from under the shell, is retried physically.
+pub fn change_dir(
+    name: &str,
+    target_dir: &Path,
+    shown: &str,
+    physical: bool,
+    pwd_state: &mut PwdState,
+) -> bool {
     let current_before_move = pwd_state.get_current_dir();
+    let logical = logical_path(&current_before_move, target_dir);
 
-    match env::set_current_dir(&target_dir) {
-        Ok(_) => {
-            if let Ok(new_current) = env::current_dir() {
-                pwd_state.set_states(new_current.display().to_string(), current_before_move);
+    if !physical && env::set_current_dir(&logical).is_ok() {
+        pwd_state.set_states(logical.display().to_string(), current_before_move);
+        record(&pwd_state.get_current_dir());
+        return true;
+    }
 
-                if !args.is_empty() && args[0] == "-" {
-                    println!("{}", pwd_state.get_current_dir());
+    match env::set_current_dir(target_dir) {
+        Ok(_) => {
+            let new_current = match env::current_dir() {
+                Ok(new_current) => new_current,
+                Err(_) => {
+                    eprintln!(
+                        "{}: error retrieving current directory: getcwd: cannot access parent directories: No such file or directory",
+                        name
+                    );
+                    logical
                 }
-            } else {
-                pwd_state.set_states(
-                    PathBuf::from(".").display().to_string(),
-                    current_before_move,
-                );
-            }
-            return true;
+            };
+            pwd_state.set_states(new_current.display().to_string(), current_before_move);
+            record(&pwd_state.get_current_dir());
+            true
         }
         Err(e) => match e.kind() {
             ErrorKind::NotFound => {
-                eprintln!("cd:  No such file or directory : {}", error_path[0]);
-                return false;
+                eprintln!("{}:  No such file or directory : {}", name, shown);
+                false
             }
             ErrorKind::PermissionDenied => {
-                eprintln!("cd: Permission denied : {}", error_path[0]);
-                return false;
+                eprintln!("{}: Permission denied : {}", name, shown);
+                false
             }
             ErrorKind::NotADirectory => {
-                eprintln!("cd: Not a directory : {}", error_path[0]);
-                return false;
+                eprintln!("{}: Not a directory : {}", name, shown);
+                false
             }
             _ => {
-                eprintln!("cd: {}: {}", error_path[0], e);
-                return false;
+                eprintln!("{}: {}: {}", name, shown, e);
+                false
             }
         },
     }
@@ -0,0 +1,403 @@
+use crate::commands::{
+    alias::{ALIAS_SPEC, UNALIAS_SPEC},
+    cat::CAT_SPEC,
+    cd::CD_SPEC,
+    cp::CP_SPEC,
+    dirs::{DIRS_SPEC, POPD_SPEC, PUSHD_SPEC},
+    echo::ECHO_SPEC,
+    exit::EXIT_SPEC,
+    hash::HASH_SPEC,
+    history::HISTORY_SPEC,
+    ls::LS_SPEC,
+    mkdir::{self, MKDIR_PARENTS_SPEC, MKDIR_SPEC},
+    mv::MV_SPEC,
+    printf::PRINTF_SPEC,
+    pwd::PWD_SPEC,
+    read::READ_SPEC,
+    rm::RM_SPEC,
+    set::SET_SPEC,
+    shell_state::ShellState,
+    test::TEST_SPEC,
+    type_builtin::{COMMAND_SPEC, TYPE_SPEC, WHICH_SPEC},
+    z::Z_SPEC,
+};
+use crate::helpers::completion::generate;
+use crate::helpers::expand::shell_quote;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Alias,
+    Builtin,
+    Command,
+    Directory,
+    File,
+    User,
+    Variable,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Alias,
+        Action::Builtin,
+        Action::Command,
+        Action::Directory,
+        Action::File,
+        Action::User,
+        Action::Variable,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Alias => "alias",
+            Action::Builtin => "builtin",
+            Action::Command => "command",
+            Action::Directory => "directory",
+            Action::File => "file",
+            Action::User => "user",
+            Action::Variable => "variable",
+        }
+    }
+
+    pub fn letter(self) -> char {
+        self.name().chars().next().unwrap_or_default()
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    pub fn from_letter(letter: char) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.letter() == letter)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompletionSpec {
+    pub options: Vec<String>,
+    pub actions: Vec<Action>,
+    pub words: Vec<String>,
+    pub command: Option<String>,
+    pub nospace: bool,
+}
+
+impl CompletionSpec {
+    fn is_empty(&self) -> bool {
+        self.options.is_empty()
+            && self.actions.is_empty()
+            && self.words.is_empty()
+            && self.command.is_none()
+    }
+}
+
+// What a builtin completes after its name: the options it accepts and what its operands
+// are. Each command module declares its own next to its option parsing.
+pub struct BuiltinSpec {
+    pub options: &'static [&'static str],
+    pub actions: &'static [Action],
+}
+
+pub const COMPLETE_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[
+        "-A", "-C", "-W", "-a", "-b", "-c", "-d", "-f", "-o", "-p", "-r", "-u", "-v",
+    ],
+    actions: &[Action::Command],
+};
+
+const NO_ARGUMENTS: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[],
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct CompletionSpecs {
+    specs: BTreeMap<String, CompletionSpec>,
+}
+
+impl CompletionSpecs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `args` are the words already typed after the command name.
+    pub fn get(&self, name: &str, args: &[String]) -> Option<CompletionSpec> {
+        self.specs
+            .get(name)
+            .cloned()
+            .or_else(|| builtin_spec(name, args))
+    }
+}
+
+fn builtin_spec(name: &str, args: &[String]) -> Option<CompletionSpec> {
+    let spec = match name {
+        "ls" => LS_SPEC,
+        "rm" => RM_SPEC,
+        "cat" => CAT_SPEC,
+        "cp" => CP_SPEC,
+        "mv" => MV_SPEC,
+        "test" | "[" => TEST_SPEC,
+        "cd" => CD_SPEC,
+        "pushd" => PUSHD_SPEC,
+        "popd" => POPD_SPEC,
+        "dirs" => DIRS_SPEC,
+        "z" | "j" => Z_SPEC,
+        "mkdir" if mkdir::parse_options(args).is_ok_and(|(parents, _)| parents) => {
+            MKDIR_PARENTS_SPEC
+        }
+        "mkdir" => MKDIR_SPEC,
+        "echo" => ECHO_SPEC,
+        "read" => READ_SPEC,
+        "set" => SET_SPEC,
+        "type" => TYPE_SPEC,
+        "which" => WHICH_SPEC,
+        "command" => COMMAND_SPEC,
+        "hash" => HASH_SPEC,
+        "history" => HISTORY_SPEC,
+        "alias" => ALIAS_SPEC,
+        "unalias" => UNALIAS_SPEC,
+        "complete" | "compgen" => COMPLETE_SPEC,
+        "pwd" => PWD_SPEC,
+        "printf" => PRINTF_SPEC,
+        "exit" => EXIT_SPEC,
+        "clear" => NO_ARGUMENTS,
+        _ => return None,
+    };
+    Some(CompletionSpec {
+        options: spec.options.iter().map(|o| o.to_string()).collect(),
+        actions: spec.actions.to_vec(),
+        ..CompletionSpec::default()
+    })
+}
+
+enum Mode {
+    Define,
+    Print,
+    Remove,
+}
+
+fn parse_spec(
+    builtin: &str,
+    args: Vec<String>,
+) -> Result<(CompletionSpec, Mode, Vec<String>), String> {
+    let mut spec = CompletionSpec::default();
+    let mut mode = Mode::Define;
+    let mut args = args.into_iter();
+    let mut names = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            names.extend(args.by_ref());
+            break;
+        }
+        if !arg.starts_with('-') || arg.len() == 1 || !names.is_empty() {
+            names.push(arg);
+            continue;
+        }
+
+        let mut flags = arg[1..].chars();
+        while let Some(flag) = flags.next() {
+            match flag {
+                'p' if builtin == "complete" => mode = Mode::Print,
+                'r' if builtin == "complete" => mode = Mode::Remove,
+                'A' | 'W' | 'C' | 'o' => {
+                    let rest: String = flags.by_ref().collect();
+                    let value = if rest.is_empty() {
+                        args.next()
+                            .ok_or_else(|| format!("-{}: option requires an argument", flag))?
+                    } else {
+                        rest
+                    };
+                    match flag {
+                        'A' => spec.actions.push(
+                            Action::from_name(&value)
+                                .ok_or_else(|| format!("{}: invalid action name", value))?,
+                        ),
+                        'W' => spec
+                            .words
+                            .extend(value.split_whitespace().map(str::to_string)),
+                        'C' => spec.command = Some(value),
+                        _ if value == "nospace" => spec.nospace = true,
+                        _ => return Err(format!("{}: invalid option name", value)),
+                    }
+                }
+                _ => match Action::from_letter(flag) {
+                    Some(action) => spec.actions.push(action),
+                    None => return Err(format!("-{}: invalid option", flag)),
+                },
+            }
+        }
+    }
+    Ok((spec, mode, names))
+}
+
+fn format_spec(name: &str, spec: &CompletionSpec) -> String {
+    let mut line = String::from("complete");
+    if spec.nospace {
+        line.push_str(" -o nospace");
+    }
+    for action in &spec.actions {
+        line.push_str(&format!(" -{}", action.letter()));
+    }
+    if !spec.words.is_empty() {
+        line.push_str(&format!(" -W {}", shell_quote(&spec.words.join(" "))));
+    }
+    if let Some(command) = &spec.command {
+        line.push_str(&format!(" -C {}", shell_quote(command)));
+    }
+    format!("{} {}", line, name)
+}
+
+pub fn complete(args: Vec<String>, state: &mut ShellState) -> bool {
+    let (spec, mode, names) = match parse_spec("complete", args) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("complete: {}", msg);
+            eprintln!(
+                "complete: usage: complete [-abcdfuv] [-pr] [-o option] [-A action] [-W wordlist] [-C command] [name ...]"
+            );
+            return false;
+        }
+    };
+    let specs = &mut state.completions.specs;
+
+    let list_all = match mode {
+        Mode::Print => names.is_empty(),
+        Mode::Define => names.is_empty() && spec.is_empty(),
+        Mode::Remove => false,
+    };
+    if list_all {
+        for (name, spec) in specs.iter() {
+            println!("{}", format_spec(name, spec));
+        }
+        return true;
+    }
+
+    match mode {
+        Mode::Remove if names.is_empty() => {
+            specs.clear();
+            true
+        }
+        Mode::Print | Mode::Remove => {
+            let mut ok = true;
+            for name in names {
+                let found = if matches!(mode, Mode::Print) {
+                    specs
+                        .get(&name)
+                        .map(|spec| println!("{}", format_spec(&name, spec)))
+                } else {
+                    specs.remove(&name).map(|_| ())
+                };
+                if found.is_none() {
+                    eprintln!("complete: {}: no completion specification", name);
+                    ok = false;
+                }
+            }
+            ok
+        }
+        Mode::Define => {
+            for name in names {
+                specs.insert(name, spec.clone());
+            }
+            true
+        }
+    }
+}
+
+pub fn compgen(args: Vec<String>, state: &ShellState) -> bool {
+    let (spec, _, words) = match parse_spec("compgen", args) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("compgen: {}", msg);
+            eprintln!(
+                "compgen: usage: compgen [-abcdfuv] [-A action] [-W wordlist] [-C command] [word]"
+            );
+            return false;
+        }
+    };
+
+    let word = words.first().map(String::as_str).unwrap_or("");
+    let candidates = generate(&spec, word, &[], "", state);
+    for candidate in &candidates {
+        println!("{}", candidate.word);
+    }
+    !candidates.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pwd_state::PwdState;
+    use crate::helpers::completion;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    fn shell() -> ShellState {
+        ShellState::new(PwdState::new("/".to_string(), "/".to_string()))
+    }
+
+    fn candidates(line: &str, state: &ShellState) -> Vec<String> {
+        completion::complete(line, state)
+            .candidates
+            .into_iter()
+            .map(|candidate| candidate.replacement)
+            .collect()
+    }
+
+    #[test]
+    fn builtins_describe_their_own_specs() {
+        let state = shell();
+        let ls = state.completions.get("ls", &[]).unwrap();
+        assert_eq!(ls.options, LS_SPEC.options);
+        assert_eq!(
+            state.completions.get("cd", &[]).unwrap().actions,
+            [Action::Directory]
+        );
+        assert!(state.completions.get("git", &[]).is_none());
+
+        let mkdir = |words: &[&str]| state.completions.get("mkdir", &args(words)).unwrap();
+        assert!(mkdir(&[]).actions.is_empty());
+        assert_eq!(mkdir(&["-p"]).actions, [Action::Directory]);
+        assert!(mkdir(&["--", "-p"]).actions.is_empty());
+    }
+
+    #[test]
+    fn dashes_complete_builtin_flags() {
+        let state = shell();
+        assert_eq!(candidates("pwd -", &state), ["-L", "-P"]);
+        assert_eq!(candidates("echo -", &state), ["-E", "-e", "-n"]);
+        assert_eq!(candidates("dirs -v", &state), ["-v"]);
+    }
+
+    #[test]
+    fn word_lists_register_and_print() {
+        let mut state = shell();
+        assert!(complete(
+            args(&["-W", "build test bench", "cargo"]),
+            &mut state
+        ));
+        assert_eq!(candidates("cargo b", &state), ["bench", "build"]);
+        assert_eq!(candidates("cargo build t", &state), ["test"]);
+
+        let spec = state.completions.get("cargo", &[]).unwrap();
+        assert_eq!(
+            format_spec("cargo", &spec),
+            "complete -W 'build test bench' cargo"
+        );
+
+        assert!(complete(args(&["-W", "x", "ls"]), &mut state));
+        assert_eq!(candidates("ls -", &state), Vec::<String>::new());
+        assert!(complete(args(&["-r", "ls", "cargo"]), &mut state));
+        assert_eq!(candidates("ls -", &state).len(), LS_SPEC.options.len());
+        assert!(state.completions.get("cargo", &[]).is_none());
+    }
+
+    #[test]
+    fn bad_specs_are_rejected() {
+        assert!(parse_spec("complete", args(&["-A", "bogus", "x"])).is_err());
+        assert!(parse_spec("complete", args(&["-W"])).is_err());
+        assert!(parse_spec("complete", args(&["-o", "filenames", "x"])).is_err());
+        assert!(parse_spec("compgen", args(&["-p"])).is_err());
+    }
+}
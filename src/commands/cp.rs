@@ -1,5 +1,11 @@
+use crate::commands::complete::{Action, BuiltinSpec};
 use std::{collections::HashSet, ffi::OsString, fs, path::Path};
 
+pub const CP_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[Action::File],
+};
+
 pub fn cp(args: Vec<String>) -> bool {
     if args.is_empty() {
         eprintln!("cp: missing file operand");
@@ -29,15 +35,19 @@ pub fn cp(args: Vec<String>) -> bool {
         for source in sources {
             let source_path = Path::new(source);
 
-            if let Some(file_name) = source_path.file_name() {
-                if !dest_seen.insert(file_name.to_os_string()) {
-                    eprintln!(
-                        "cp: warning: cannot copy '{}' to '{}': destination file already used by another argument",
-                        source.replace("\n", "\\n"),
-                        destination_path.join(file_name).display().to_string().replace("\n", "\\n")
-                    );
-                    continue;
-                }
+            if let Some(file_name) = source_path.file_name()
+                && !dest_seen.insert(file_name.to_os_string())
+            {
+                eprintln!(
+                    "cp: warning: cannot copy '{}' to '{}': destination file already used by another argument",
+                    source.replace("\n", "\\n"),
+                    destination_path
+                        .join(file_name)
+                        .display()
+                        .to_string()
+                        .replace("\n", "\\n")
+                );
+                continue;
             }
 
             copy_file_logic(source_path, destination_path, true);
@@ -80,17 +90,16 @@ fn copy_file_logic(source: &Path, destination: &Path, dest_is_dir: bool) {
         destination.to_path_buf()
     };
 
-    if final_dest.exists() {
-        if let (Ok(src_can), Ok(dst_can)) = (source.canonicalize(), final_dest.canonicalize()) {
-            if src_can == dst_can {
-                eprintln!(
-                    "cp: '{}' and '{}' are the same file",
-                    source.display().to_string().replace("\n", "\\n"),
-                    final_dest.display().to_string().replace("\n", "\\n")
-                );
-                return;
-            }
-        }
+    if final_dest.exists()
+        && let (Ok(src_can), Ok(dst_can)) = (source.canonicalize(), final_dest.canonicalize())
+        && src_can == dst_can
+    {
+        eprintln!(
+            "cp: '{}' and '{}' are the same file",
+            source.display().to_string().replace("\n", "\\n"),
+            final_dest.display().to_string().replace("\n", "\\n")
+        );
+        return;
     }
 
     if let Err(e) = fs::copy(source, &final_dest) {
@@ -0,0 +1,209 @@
+use crate::commands::cd::change_dir;
+use crate::commands::complete::{Action, BuiltinSpec};
+use crate::commands::pwd_state::PwdState;
+use crate::helpers::prompt::abbreviate_home;
+use std::path::Path;
+
+pub const PUSHD_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[Action::Directory],
+};
+
+pub const POPD_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[],
+};
+
+pub const DIRS_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-c", "-l", "-p", "-v"],
+    actions: &[],
+};
+
+fn is_index(arg: &str) -> bool {
+    arg.len() > 1 && arg.starts_with(['+', '-']) && arg[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+fn print_dirs(pwd_state: &PwdState) {
+    let dirs: Vec<String> = pwd_state
+        .get_dirs()
+        .iter()
+        .map(|dir| abbreviate_home(dir))
+        .collect();
+    println!("{}", dirs.join(" "));
+}
+
+pub fn pushd(args: Vec<String>, pwd_state: &mut PwdState) -> bool {
+    if args.len() > 1 {
+        eprintln!("pushd: too many arguments");
+        return false;
+    }
+    let mut dirs = pwd_state.get_dirs();
+    match args.first().map(String::as_str) {
+        None if dirs.len() < 2 => {
+            eprintln!("pushd: no other directory");
+            return false;
+        }
+        None => dirs.swap(0, 1),
+        Some(spec) if is_index(spec) => match pwd_state.stack_index(spec) {
+            Some(n) => dirs = pwd_state.rotated(n),
+            None => {
+                eprintln!("pushd: {}: directory stack index out of range", spec);
+                return false;
+            }
+        },
+        Some(dir) => {
+            let previous = pwd_state.get_current_dir();
+            if !change_dir("pushd", Path::new(dir), dir, false, pwd_state) {
+                return false;
+            }
+            pwd_state.stack_mut().insert(0, previous);
+            print_dirs(pwd_state);
+            return true;
+        }
+    }
+
+    if !change_dir("pushd", Path::new(&dirs[0]), &dirs[0], false, pwd_state) {
+        return false;
+    }
+    *pwd_state.stack_mut() = dirs.split_off(1);
+    print_dirs(pwd_state);
+    true
+}
+
+pub fn popd(args: Vec<String>, pwd_state: &mut PwdState) -> bool {
+    if args.len() > 1 {
+        eprintln!("popd: too many arguments");
+        return false;
+    }
+    if pwd_state.stack_mut().is_empty() {
+        eprintln!("popd: directory stack empty");
+        return false;
+    }
+    let index = match args.first() {
+        None => 0,
+        Some(spec) if is_index(spec) => match pwd_state.stack_index(spec) {
+            Some(n) => n,
+            None => {
+                eprintln!("popd: {}: directory stack index out of range", spec);
+                return false;
+            }
+        },
+        Some(arg) => {
+            eprintln!("popd: {}: invalid argument", arg);
+            return false;
+        }
+    };
+
+    // Dropping the current directory means moving into the next one down.
+    if index == 0 {
+        let next = pwd_state.stack_mut()[0].clone();
+        if !change_dir("popd", Path::new(&next), &next, false, pwd_state) {
+            return false;
+        }
+        pwd_state.stack_mut().remove(0);
+    } else {
+        pwd_state.stack_mut().remove(index - 1);
+    }
+    print_dirs(pwd_state);
+    true
+}
+
+pub fn dirs(args: Vec<String>, pwd_state: &mut PwdState) -> bool {
+    let (mut clear, mut long, mut verbose, mut per_line) = (false, false, false, false);
+    let mut index = None;
+    for arg in &args {
+        if is_index(arg) {
+            match pwd_state.stack_index(arg) {
+                Some(n) => index = Some(n),
+                None => {
+                    eprintln!("dirs: {}: directory stack index out of range", arg);
+                    return false;
+                }
+            }
+            continue;
+        }
+        let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty()) else {
+            eprintln!("dirs: {}: invalid argument", arg);
+            eprintln!("dirs: usage: dirs [-clpv] [+N] [-N]");
+            return false;
+        };
+        for flag in flags.chars() {
+            match flag {
+                'c' => clear = true,
+                'l' => long = true,
+                'v' => verbose = true,
+                'p' => per_line = true,
+                _ => {
+                    eprintln!("dirs: -{}: invalid option", flag);
+                    eprintln!("dirs: usage: dirs [-clpv] [+N] [-N]");
+                    return false;
+                }
+            }
+        }
+    }
+
+    if clear {
+        pwd_state.stack_mut().clear();
+        return true;
+    }
+    let dirs: Vec<String> = pwd_state
+        .get_dirs()
+        .iter()
+        .map(|dir| {
+            if long {
+                dir.clone()
+            } else {
+                abbreviate_home(dir)
+            }
+        })
+        .collect();
+    if let Some(n) = index {
+        println!("{}", dirs[n]);
+    } else if verbose {
+        for (i, dir) in dirs.iter().enumerate() {
+            println!("{:2}  {}", i, dir);
+        }
+    } else if per_line {
+        for dir in &dirs {
+            println!("{}", dir);
+        }
+    } else {
+        println!("{}", dirs.join(" "));
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stacked(dirs: &[&str]) -> PwdState {
+        let mut state = PwdState::new(dirs[0].to_string(), "/".to_string());
+        state
+            .stack_mut()
+            .extend(dirs[1..].iter().map(|dir| dir.to_string()));
+        state
+    }
+
+    #[test]
+    fn popd_drops_entries_below_the_top_by_index() {
+        let mut state = stacked(&["/a", "/b", "/c", "/d"]);
+        assert!(popd(vec!["+2".to_string()], &mut state));
+        assert_eq!(state.get_dirs(), ["/a", "/b", "/d"]);
+        assert!(popd(vec!["-0".to_string()], &mut state));
+        assert_eq!(state.get_dirs(), ["/a", "/b"]);
+        assert!(!popd(vec!["+5".to_string()], &mut state));
+        assert!(!popd(vec!["-18446744073709551615".to_string()], &mut state));
+        assert_eq!(state.get_dirs(), ["/a", "/b"]);
+    }
+
+    #[test]
+    fn dirs_rejects_out_of_range_indexes() {
+        let mut state = stacked(&["/a", "/b"]);
+        assert!(dirs(vec!["+1".to_string()], &mut state));
+        assert!(!dirs(vec!["-2".to_string()], &mut state));
+        assert!(!pushd(vec!["+2".to_string()], &mut state));
+        assert!(dirs(vec!["-c".to_string()], &mut state));
+        assert_eq!(state.get_dirs(), ["/a"]);
+    }
+}
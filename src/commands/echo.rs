@@ -1,5 +1,151 @@
+use crate::commands::complete::{Action, BuiltinSpec};
+use std::io::{self, Write};
+
+pub const ECHO_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-n", "-e", "-E"],
+    actions: &[Action::File],
+};
+
 pub fn echo(args: Vec<String>) {
-    let buffer = args.join(" ");
+    let out = render(&args);
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&out).ok();
+    stdout.flush().ok();
+}
+
+fn render(args: &[String]) -> Vec<u8> {
+    let mut newline = true;
+    let mut escapes = false;
+    let mut first_word = 0;
+
+    for arg in args {
+        let is_flag = arg.len() > 1
+            && arg.starts_with('-')
+            && arg[1..].chars().all(|c| matches!(c, 'n' | 'e' | 'E'));
+        if !is_flag {
+            break;
+        }
+        for c in arg[1..].chars() {
+            match c {
+                'n' => newline = false,
+                'e' => escapes = true,
+                _ => escapes = false,
+            }
+        }
+        first_word += 1;
+    }
+
+    let buffer = args[first_word..].join(" ");
+    let mut out = Vec::new();
+    if escapes {
+        if interpret_escapes(&buffer, true, &mut out) {
+            newline = false;
+        }
+    } else {
+        out.extend_from_slice(buffer.as_bytes());
+    }
+    if newline {
+        out.push(b'\n');
+    }
+    out
+}
+
+// Returns `true` when `\c` asks to stop all further output. `echo_style` reads octal
+// escapes as echo's `\0nnn` rather than printf's `\nnn`.
+pub fn interpret_escapes(text: &str, echo_style: bool, out: &mut Vec<u8>) -> bool {
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+
+        let Some(next) = chars.next() else {
+            out.push(b'\\');
+            break;
+        };
+        match next {
+            'a' => out.push(0x07),
+            'b' => out.push(0x08),
+            'e' | 'E' => out.push(0x1b),
+            'f' => out.push(0x0c),
+            'n' => out.push(b'\n'),
+            'r' => out.push(b'\r'),
+            't' => out.push(b'\t'),
+            'v' => out.push(0x0b),
+            '\\' => out.push(b'\\'),
+            'c' => return true,
+            '0'..='7' if !echo_style || next == '0' => {
+                let mut value = if echo_style {
+                    0
+                } else {
+                    next.to_digit(8).unwrap()
+                };
+                for _ in 0..(if echo_style { 3 } else { 2 }) {
+                    match chars.peek().and_then(|d| d.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                out.push(value as u8);
+            }
+            'x' => {
+                let mut value = 0;
+                let mut digits = 0;
+                while digits < 2 {
+                    match chars.peek().and_then(|d| d.to_digit(16)) {
+                        Some(digit) => {
+                            value = value * 16 + digit;
+                            chars.next();
+                            digits += 1;
+                        }
+                        None => break,
+                    }
+                }
+                if digits == 0 {
+                    out.extend_from_slice(b"\\x");
+                } else {
+                    out.push(value as u8);
+                }
+            }
+            _ => {
+                out.push(b'\\');
+                out.extend_from_slice(next.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo_output(args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        String::from_utf8(render(&args)).unwrap()
+    }
+
+    #[test]
+    fn flags_toggle_newline_and_escapes() {
+        assert_eq!(echo_output(&["a", "b"]), "a b\n");
+        assert_eq!(echo_output(&["-n", "a"]), "a");
+        assert_eq!(echo_output(&["-e", "a\\tb"]), "a\tb\n");
+        assert_eq!(echo_output(&["-eE", "a\\tb"]), "a\\tb\n");
+        assert_eq!(echo_output(&["-ne", "x"]), "x");
+        assert_eq!(echo_output(&["-x", "a"]), "-x a\n");
+        assert_eq!(echo_output(&["a", "-n"]), "a -n\n");
+    }
 
-    println!("{}", buffer);
+    #[test]
+    fn escapes_follow_echo_rules() {
+        assert_eq!(echo_output(&["-e", "\\0101\\x42\\101"]), "AB\\101\n");
+        assert_eq!(echo_output(&["-e", "a\\cb"]), "a");
+        assert_eq!(echo_output(&["-e", "\\q\\\\"]), "\\q\\\n");
+        assert_eq!(echo_output(&["-e", "\\x"]), "\\x\n");
+    }
 }
@@ -1,3 +1,10 @@
+use crate::commands::complete::BuiltinSpec;
+
+pub const EXIT_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[],
+};
+
 pub fn exit() -> String {
     std::process::exit(0);
 }
@@ -0,0 +1,141 @@
+use crate::commands::complete::{Action, BuiltinSpec};
+use std::collections::BTreeMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+pub const HASH_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-d", "-r", "-t"],
+    actions: &[Action::Command],
+};
+
+#[derive(Debug, Clone)]
+struct HashEntry {
+    path: PathBuf,
+    hits: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HashTable {
+    entries: BTreeMap<String, HashEntry>,
+}
+
+impl HashTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn find(&mut self, name: &str) -> Option<PathBuf> {
+        if name.contains('/') {
+            return Some(PathBuf::from(name));
+        }
+
+        if let Some(entry) = self.entries.get_mut(name)
+            && is_executable(&entry.path)
+        {
+            entry.hits += 1;
+            return Some(entry.path.clone());
+        }
+
+        let path = search_path(name)?;
+        self.entries.insert(
+            name.to_string(),
+            HashEntry {
+                path: path.clone(),
+                hits: 1,
+            },
+        );
+        Some(path)
+    }
+
+    pub fn cached(&self, name: &str) -> Option<&Path> {
+        self.entries.get(name).map(|entry| entry.path.as_path())
+    }
+
+    pub fn remember(&mut self, name: &str) -> bool {
+        match search_path(name) {
+            Some(path) => {
+                self.entries
+                    .insert(name.to_string(), HashEntry { path, hits: 0 });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn forget(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+pub fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+pub fn search_path_all(name: &str) -> Vec<PathBuf> {
+    let path_var = env::var("PATH").unwrap_or_default();
+    path_var
+        .split(':')
+        .map(|dir| if dir.is_empty() { "." } else { dir })
+        .map(|dir| Path::new(dir).join(name))
+        .filter(|candidate| is_executable(candidate))
+        .collect()
+}
+
+pub fn search_path(name: &str) -> Option<PathBuf> {
+    search_path_all(name).into_iter().next()
+}
+
+pub fn hash(args: Vec<String>, table: &mut HashTable) -> bool {
+    if args.is_empty() {
+        if table.entries.is_empty() {
+            println!("hash: hash table empty");
+            return true;
+        }
+        println!("hits\tcommand");
+        for entry in table.entries.values() {
+            println!("{:>4}\t{}", entry.hits, entry.path.display());
+        }
+        return true;
+    }
+
+    let mut ok = true;
+    let mut delete = false;
+    let mut print = false;
+    for arg in args {
+        match arg.as_str() {
+            "-r" => table.clear(),
+            "-d" => delete = true,
+            "-t" => print = true,
+            _ if arg.starts_with('-') => {
+                eprintln!("hash: {}: invalid option", arg);
+                eprintln!("hash: usage: hash [-r] [-d] [-t] [name ...]");
+                return false;
+            }
+            _ if delete => {
+                if !table.forget(&arg) {
+                    eprintln!("hash: {}: not found", arg);
+                    ok = false;
+                }
+            }
+            _ if print => match table.cached(&arg) {
+                Some(path) => println!("{}", path.display()),
+                None => {
+                    eprintln!("hash: {}: not found", arg);
+                    ok = false;
+                }
+            },
+            _ => {
+                if !table.remember(&arg) {
+                    eprintln!("hash: {}: not found", arg);
+                    ok = false;
+                }
+            }
+        }
+    }
+    ok
+}
@@ -0,0 +1,456 @@
+use crate::commands::complete::BuiltinSpec;
+use crate::helpers::parser::last_word;
+use chrono::{Local, TimeZone};
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::path::PathBuf;
+
+pub const HISTORY_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-c", "-d"],
+    actions: &[],
+};
+
+const DEFAULT_HISTSIZE: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub line: String,
+    pub timestamp: i64,
+    pub cwd: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    path: Option<PathBuf>,
+    saved: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load() -> Self {
+        let path = env::var("HISTFILE")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var("HOME")
+                    .ok()
+                    .map(|h| PathBuf::from(h).join(".0shell_history"))
+            });
+
+        let mut history = Self {
+            entries: Vec::new(),
+            path,
+            saved: 0,
+        };
+        let Some(contents) = history
+            .path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+        else {
+            return history;
+        };
+
+        history.entries = parse(&contents);
+        history.saved = history.entries.len();
+        history.truncate(limit("HISTSIZE"));
+        history
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(|e| e.line.as_str())
+    }
+
+    pub fn last(&self) -> Option<&str> {
+        self.entries.last().map(|e| e.line.as_str())
+    }
+
+    pub fn add(&mut self, line: &str, cwd: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let control = env::var("HISTCONTROL").unwrap_or_default();
+        let has = |option: &str| control.split(':').any(|c| c == option);
+        let ignore_space = has("ignorespace") || has("ignoreboth");
+        let ignore_dups = has("ignoredups") || has("ignoreboth");
+
+        if ignore_space && line.starts_with(' ') {
+            return;
+        }
+        if ignore_dups && self.last() == Some(line) {
+            return;
+        }
+        let count = self.entries.len();
+        if has("erasedups") {
+            self.entries.retain(|e| e.line != line);
+        }
+        let erased = self.entries.len() < count;
+
+        self.entries.push(HistoryEntry {
+            line: line.to_string(),
+            timestamp: Local::now().timestamp(),
+            cwd: Some(cwd.to_string()).filter(|cwd| !cwd.contains('\n')),
+        });
+        self.truncate(limit("HISTSIZE"));
+        if erased {
+            self.save();
+        } else {
+            self.append();
+        }
+    }
+
+    // The newest entry extending `prefix` by a single line, preferring ones recorded in
+    // `cwd`. Returns only the part still to be typed.
+    pub fn suggest(&self, prefix: &str, cwd: &str) -> Option<&str> {
+        if prefix.trim().is_empty() {
+            return None;
+        }
+        let mut candidates = self.entries.iter().rev().filter_map(|entry| {
+            let rest = entry.line.strip_prefix(prefix)?;
+            (!rest.is_empty() && !rest.contains('\n')).then_some((entry, rest))
+        });
+        let newest = candidates.next()?;
+        match newest.0.cwd.as_deref() {
+            Some(dir) if dir == cwd => Some(newest.1),
+            _ => Some(
+                candidates
+                    .find(|(entry, _)| entry.cwd.as_deref() == Some(cwd))
+                    .unwrap_or(newest)
+                    .1,
+            ),
+        }
+    }
+
+    // Adds the newest entry to the end of the file, rewriting it only once it would hold
+    // more than HISTFILESIZE entries.
+    fn append(&mut self) {
+        let (Some(path), Some(entry)) = (&self.path, self.entries.last()) else {
+            return;
+        };
+        if self.saved >= file_limit() {
+            self.save();
+            return;
+        }
+        let appended = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(record(entry).as_bytes()));
+        if appended.is_ok() {
+            self.saved += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    pub fn delete(&mut self, index: usize) {
+        self.entries.remove(index);
+        self.save();
+    }
+
+    fn truncate(&mut self, max: usize) {
+        if self.entries.len() > max {
+            self.entries.drain(..self.entries.len() - max);
+        }
+    }
+
+    fn save(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let skip = self.entries.len().saturating_sub(file_limit());
+
+        let contents: String = self.entries[skip..].iter().map(record).collect();
+        if fs::write(path, contents).is_ok() {
+            self.saved = self.entries.len() - skip;
+        }
+    }
+}
+
+// Each entry is written as a `#timestamp [cwd]` marker followed by its lines. Lines of the
+// command that start with `#` get one more, so they can't be mistaken for the next marker.
+fn record(entry: &HistoryEntry) -> String {
+    let mut record = match &entry.cwd {
+        Some(cwd) => format!("#{} {}\n", entry.timestamp, cwd),
+        None => format!("#{}\n", entry.timestamp),
+    };
+    for line in entry.line.lines() {
+        if line.starts_with('#') {
+            record.push('#');
+        }
+        record.push_str(line);
+        record.push('\n');
+    }
+    record
+}
+
+// Lines before the first marker come from a file without timestamps and are one entry each.
+fn parse(contents: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut timestamp = 0;
+    let mut cwd = None;
+    let mut stamped = false;
+    let mut continuing = false;
+    for line in contents.lines() {
+        let mut text = line;
+        if let Some(marker) = line.strip_prefix('#') {
+            let (ts, dir) = match marker.split_once(' ') {
+                Some((ts, dir)) => (ts, Some(dir)),
+                None => (marker, None),
+            };
+            if let Ok(ts) = ts.parse() {
+                timestamp = ts;
+                cwd = dir.map(str::to_string);
+                stamped = true;
+                continuing = false;
+                continue;
+            }
+            text = marker;
+        }
+        if continuing && let Some(entry) = entries.last_mut() {
+            entry.line.push('\n');
+            entry.line.push_str(text);
+            continue;
+        }
+        continuing = std::mem::take(&mut stamped);
+        entries.push(HistoryEntry {
+            line: text.to_string(),
+            timestamp,
+            cwd: cwd.take(),
+        });
+    }
+    entries
+}
+
+fn file_limit() -> usize {
+    env::var("HISTFILESIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| limit("HISTSIZE"))
+}
+
+fn limit(name: &str) -> usize {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_HISTSIZE)
+}
+
+pub fn history(args: Vec<String>, history: &mut History) -> bool {
+    let mut args = args.into_iter();
+    let mut count = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" => {
+                history.clear();
+                return true;
+            }
+            "-d" => {
+                let Some(offset) = args.next() else {
+                    eprintln!("history: -d: option requires an argument");
+                    return false;
+                };
+                let index = offset.parse::<i64>().ok().and_then(|n| {
+                    let len = history.len() as i64;
+                    let index = if n < 0 { len + n } else { n - 1 };
+                    (0..len).contains(&index).then_some(index as usize)
+                });
+                match index {
+                    Some(index) => history.delete(index),
+                    None => {
+                        eprintln!("history: {}: history position out of range", offset);
+                        return false;
+                    }
+                }
+                return true;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                eprintln!("history: {}: invalid option", arg);
+                eprintln!("history: usage: history [-c] [-d offset] [n]");
+                return false;
+            }
+            _ => match arg.parse::<usize>() {
+                Ok(n) => count = Some(n),
+                Err(_) => {
+                    eprintln!("history: {}: numeric argument required", arg);
+                    return false;
+                }
+            },
+        }
+    }
+
+    let time_format = env::var("HISTTIMEFORMAT").ok();
+    let skip = history.len().saturating_sub(count.unwrap_or(history.len()));
+    for (i, entry) in history.entries.iter().enumerate().skip(skip) {
+        let mut time = String::new();
+        if let Some(format) = &time_format
+            && let Some(datetime) = Local.timestamp_opt(entry.timestamp, 0).single()
+        {
+            write!(time, "{}", datetime.format(format)).ok();
+        }
+        println!("{:>5}  {}{}", i + 1, time, entry.line);
+    }
+    true
+}
+
+pub fn expand_history(line: &str, history: &History) -> Result<Option<String>, String> {
+    if let Some(rest) = line.strip_prefix('^') {
+        return quick_substitution(rest, history).map(Some);
+    }
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut changed = false;
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if !in_single => {
+                out.push(c);
+                if let Some(&next) = chars.get(i + 1) {
+                    out.push(next);
+                }
+                i += 2;
+                continue;
+            }
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            '!' if !in_single => {
+                let designator = match chars.get(i + 1) {
+                    None => None,
+                    Some(n) if n.is_whitespace() || matches!(n, '=' | '(' | '"') => None,
+                    Some('!') | Some('$') => Some(chars[i + 1..i + 2].iter().collect()),
+                    Some(_) => {
+                        let len = chars[i + 1..]
+                            .iter()
+                            .take_while(|c| !c.is_whitespace() && !";&|<>()'\"".contains(**c))
+                            .count();
+                        Some(chars[i + 1..i + 1 + len].iter().collect::<String>())
+                    }
+                };
+                if let Some(designator) = designator {
+                    out.push_str(&resolve_event(&designator, history)?);
+                    i += 1 + designator.chars().count();
+                    changed = true;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+
+    Ok(changed.then_some(out))
+}
+
+fn resolve_event(designator: &str, history: &History) -> Result<String, String> {
+    let not_found = || format!("!{}: event not found", designator);
+    let event = match designator {
+        "!" => history.last(),
+        "$" => {
+            return history.last().and_then(last_word).ok_or_else(not_found);
+        }
+        _ => match designator.parse::<i64>() {
+            Ok(n) if n < 0 => history
+                .len()
+                .checked_sub(n.unsigned_abs() as usize)
+                .and_then(|i| history.get(i)),
+            Ok(n) => (n as usize).checked_sub(1).and_then(|i| history.get(i)),
+            Err(_) => history
+                .entries
+                .iter()
+                .rev()
+                .map(|e| e.line.as_str())
+                .find(|line| line.starts_with(designator)),
+        },
+    };
+    event.map(str::to_string).ok_or_else(not_found)
+}
+
+fn quick_substitution(rest: &str, history: &History) -> Result<String, String> {
+    let mut parts = rest.splitn(3, '^');
+    let old = parts.next().unwrap_or("");
+    let new = parts.next().unwrap_or("");
+    let tail = parts.next().unwrap_or("");
+
+    let failed = || format!("^{}: substitution failed", rest);
+    let last = history.last().ok_or_else(failed)?;
+    if old.is_empty() || !last.contains(old) {
+        return Err(failed());
+    }
+    Ok(format!("{}{}", last.replacen(old, new, 1), tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(line: &str, timestamp: i64, cwd: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            line: line.to_string(),
+            timestamp,
+            cwd: cwd.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn records_round_trip_through_parse() {
+        let entries = [
+            entry("echo one", 1, Some("/tmp")),
+            entry("for x in a\n#42\n#7 /home\n## done", 2, None),
+            entry("#123", 3, Some("/a b")),
+            entry("printf '%s\n' x\n\ny", 4, None),
+        ];
+        let contents: String = entries.iter().map(record).collect();
+        let parsed = parse(&contents);
+
+        assert_eq!(parsed.len(), entries.len());
+        for (parsed, entry) in parsed.iter().zip(&entries) {
+            assert_eq!(parsed.line, entry.line);
+            assert_eq!(parsed.timestamp, entry.timestamp);
+            assert_eq!(parsed.cwd, entry.cwd);
+        }
+    }
+
+    #[test]
+    fn last_word_skips_operators() {
+        let mut history = History::new();
+        history.add("make && cd build;", "/");
+        assert_eq!(
+            expand_history("ls !$", &history),
+            Ok(Some("ls build".to_string()))
+        );
+        history.add("echo hi > 'out file' ||", "/");
+        assert_eq!(resolve_event("$", &history), Ok("'out file'".to_string()));
+    }
+
+    #[test]
+    fn unstamped_lines_are_separate_entries() {
+        let parsed = parse("ls\ncd /tmp\n");
+        let lines: Vec<&str> = parsed.iter().map(|e| e.line.as_str()).collect();
+        assert_eq!(lines, ["ls", "cd /tmp"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::commands::complete::{Action, BuiltinSpec};
 use chrono::{DateTime, Duration, Local};
 use std::cmp::max;
 use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
@@ -5,6 +6,11 @@ use std::time::SystemTime;
 use std::{fs, path::Path};
 use users::{get_group_by_gid, get_user_by_uid};
 
+pub const LS_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-a", "-l", "-F"],
+    actions: &[Action::File],
+};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Flag {
     pub a: bool,
@@ -219,26 +225,24 @@ fn run_ls_l(path: &str, flag: Flag) -> String {
 fn get_dir_content(path: &str, show_hidden: bool) -> Result<Vec<String>, bool> {
     let mut filenames = Vec::new();
     if show_hidden {
-        if let Ok(_) = fs::metadata(path) {
+        if fs::metadata(path).is_ok() {
             filenames.push(".".to_string());
         }
 
         let parent_path = Path::new(path).join("..");
-        if let Ok(_) = fs::metadata(&parent_path) {
+        if fs::metadata(&parent_path).is_ok() {
             filenames.push("..".to_string());
         }
     }
     match fs::read_dir(path) {
         Ok(entries) => {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let name = entry.file_name();
-                    if let Ok(name_str) = name.into_string() {
-                        if !show_hidden && name_str.starts_with('.') {
-                            continue;
-                        }
-                        filenames.push(name_str);
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                if let Ok(name_str) = name.into_string() {
+                    if !show_hidden && name_str.starts_with('.') {
+                        continue;
                     }
+                    filenames.push(name_str);
                 }
             }
         }
@@ -269,8 +273,9 @@ fn get_dir_content(path: &str, show_hidden: bool) -> Result<Vec<String>, bool> {
     Ok(filenames)
 }
 
-fn is_flag(arg: &String, flag: &mut Flag) -> bool {
-    if arg.len() > 1 && arg[1..].chars().all(|c| "alF".contains(c)) {
+fn is_flag(arg: &str, flag: &mut Flag) -> bool {
+    let known = |c: char| LS_SPEC.options.contains(&format!("-{}", c).as_str());
+    if arg.len() > 1 && arg[1..].chars().all(known) {
         for c in arg[1..].chars() {
             match c {
                 'a' => flag.a = true,
@@ -456,26 +461,26 @@ fn prepare_long_entry(
         name = append_indicator(name, metadata);
     }
 
-    if metadata.file_type().is_symlink() {
-        if let Ok(target_path_buf) = fs::read_link(full_path) {
-            let mut target_str = target_path_buf.to_string_lossy().to_string();
-            if flag.f {
-                let resolved_target = if target_path_buf.is_absolute() {
-                    target_path_buf.clone()
-                } else {
-                    full_path
-                        .parent()
-                        .unwrap_or(Path::new("."))
-                        .join(&target_path_buf)
-                };
-
-                if let Ok(target_meta) = fs::metadata(&resolved_target) {
-                    target_str = append_indicator(target_str, &target_meta);
-                }
+    if metadata.file_type().is_symlink()
+        && let Ok(target_path_buf) = fs::read_link(full_path)
+    {
+        let mut target_str = target_path_buf.to_string_lossy().to_string();
+        if flag.f {
+            let resolved_target = if target_path_buf.is_absolute() {
+                target_path_buf.clone()
+            } else {
+                full_path
+                    .parent()
+                    .unwrap_or(Path::new("."))
+                    .join(&target_path_buf)
+            };
+
+            if let Ok(target_meta) = fs::metadata(&resolved_target) {
+                target_str = append_indicator(target_str, &target_meta);
             }
-            name.push_str(" -> ");
-            name.push_str(&target_str);
         }
+        name.push_str(" -> ");
+        name.push_str(&target_str);
     }
 
     let perms = format_permissions(metadata, full_path);
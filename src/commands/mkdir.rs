@@ -0,0 +1,79 @@
+use crate::commands::complete::{Action, BuiltinSpec};
+use std::fs;
+
+pub const MKDIR_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-p"],
+    actions: &[],
+};
+
+// With -p the operand may sit under directories that already exist, so those are offered.
+pub const MKDIR_PARENTS_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-p"],
+    actions: &[Action::Directory],
+};
+
+// Options end at the first operand or `--`. Returns whether -p was given and how many
+// words the options used, or the first unknown option letter.
+pub fn parse_options(args: &[String]) -> Result<(bool, usize), char> {
+    let mut parents = false;
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--" {
+            return Ok((parents, i + 1));
+        }
+        let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+            return Ok((parents, i));
+        };
+        if let Some(unknown) = flags.chars().find(|&c| c != 'p') {
+            return Err(unknown);
+        }
+        parents = true;
+    }
+    Ok((parents, args.len()))
+}
+
+pub fn mkdir(dir: Vec<String>, error_dir: Vec<String>) -> bool {
+    let (parents, operands) = match parse_options(&dir) {
+        Ok(parsed) => parsed,
+        Err(unknown) => {
+            eprintln!("mkdir: invalid option -- '{}'", unknown);
+            return false;
+        }
+    };
+    if dir.len() == operands {
+        println!("mkdir: missing operand");
+        return false;
+    }
+    for (d, shown) in dir[operands..].iter().zip(&error_dir[operands..]) {
+        let created = if parents {
+            fs::create_dir_all(d)
+        } else {
+            fs::create_dir(d)
+        };
+        if let Err(e) = created {
+            eprintln!("mkdir: cannot create directory '{}': {}", shown, e);
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Result<(bool, usize), char> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_options(&args)
+    }
+
+    #[test]
+    fn options_stop_at_the_first_operand() {
+        assert_eq!(options(&["-p", "a"]), Ok((true, 1)));
+        assert_eq!(options(&["-pp", "a"]), Ok((true, 1)));
+        assert_eq!(options(&["a", "-p"]), Ok((false, 0)));
+        assert_eq!(options(&["--", "-p"]), Ok((false, 1)));
+        assert_eq!(options(&["-p", "--", "-p"]), Ok((true, 2)));
+        assert_eq!(options(&["-", "a"]), Ok((false, 0)));
+        assert_eq!(options(&["-px"]), Err('x'));
+    }
+}
@@ -1,9 +1,24 @@
+pub mod alias;
 pub mod cat;
 pub mod cd;
+pub mod complete;
 pub mod cp;
+pub mod dirs;
 pub mod echo;
 pub mod exit;
+pub mod hash;
+pub mod history;
 pub mod ls;
+pub mod mkdir;
 pub mod mv;
+pub mod printf;
+pub mod pwd;
 pub mod pwd_state;
+pub mod read;
 pub mod rm;
+pub mod set;
+pub mod shell_options;
+pub mod shell_state;
+pub mod test;
+pub mod type_builtin;
+pub mod z;
@@ -1,6 +1,12 @@
+use crate::commands::complete::{Action, BuiltinSpec};
 use std::fs;
 use std::path::Path;
 
+pub const MV_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[Action::File],
+};
+
 pub fn mv(args: Vec<String>) -> bool {
     if args.len() < 2 {
         println!("mv: missing operand");
@@ -0,0 +1,457 @@
+use crate::commands::complete::BuiltinSpec;
+use crate::commands::echo::interpret_escapes;
+use crate::helpers::expand::shell_quote;
+use std::io::{self, Write};
+
+pub const PRINTF_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[],
+};
+
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+pub fn printf(args: Vec<String>) -> bool {
+    let args = match args.first() {
+        Some(first) if first == "--" => &args[1..],
+        _ => &args[..],
+    };
+    let Some((format, rest)) = args.split_first() else {
+        eprintln!("printf: usage: printf format [arguments]");
+        return false;
+    };
+
+    let (out, ok) = render(format, rest);
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&out).ok();
+    stdout.flush().ok();
+    ok
+}
+
+// The format is reused until every argument has been consumed.
+fn render(format: &str, args: &[String]) -> (Vec<u8>, bool) {
+    let mut out = Vec::new();
+    let mut ok = true;
+    let mut rest = args.iter();
+
+    loop {
+        let remaining = rest.len();
+        if format_once(format, &mut rest, &mut out, &mut ok).is_err() {
+            break;
+        }
+        if rest.len() == 0 || rest.len() == remaining {
+            break;
+        }
+    }
+    (out, ok)
+}
+
+// Returns `Err` when a `\c`, in the format or inside `%b`, stops all further output.
+fn format_once(
+    format: &str,
+    args: &mut std::slice::Iter<String>,
+    out: &mut Vec<u8>,
+    ok: &mut bool,
+) -> Result<(), ()> {
+    let mut chars = format.chars().peekable();
+    let mut literal = String::new();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            literal.push(c);
+            if let Some(next) = chars.next() {
+                literal.push(next);
+            }
+            continue;
+        }
+        if c != '%' {
+            literal.push(c);
+            continue;
+        }
+
+        if interpret_escapes(&literal, false, out) {
+            return Err(());
+        }
+        literal.clear();
+
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            out.push(b'%');
+            continue;
+        }
+
+        let mut spec = Spec::default();
+        while let Some(&flag) = chars.peek() {
+            match flag {
+                '-' => spec.left = true,
+                '+' => spec.plus = true,
+                ' ' => spec.space = true,
+                '#' => spec.alt = true,
+                '0' => spec.zero = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        if chars.peek() == Some(&'*') {
+            chars.next();
+            let width = parse_integer(args.next().map(String::as_str).unwrap_or(""), ok);
+            if width < 0 {
+                spec.left = true;
+            }
+            spec.width = Some(width.unsigned_abs() as usize);
+        } else {
+            spec.width = read_number(&mut chars);
+        }
+
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            if chars.peek() == Some(&'*') {
+                chars.next();
+                let precision = parse_integer(args.next().map(String::as_str).unwrap_or(""), ok);
+                spec.precision = Some(precision.max(0) as usize);
+            } else {
+                spec.precision = Some(read_number(&mut chars).unwrap_or(0));
+            }
+        }
+
+        while matches!(chars.peek(), Some('h' | 'l' | 'L' | 'j' | 'z' | 't')) {
+            chars.next();
+        }
+
+        let Some(conversion) = chars.next() else {
+            eprintln!("printf: %: invalid format character");
+            *ok = false;
+            return Ok(());
+        };
+        let arg = args.next().map(String::as_str);
+
+        let text = match conversion {
+            's' => {
+                let s = arg.unwrap_or("");
+                match spec.precision {
+                    Some(p) => s.chars().take(p).collect(),
+                    None => s.to_string(),
+                }
+            }
+            'b' => {
+                let mut expanded = Vec::new();
+                let stop = interpret_escapes(arg.unwrap_or(""), true, &mut expanded);
+                pad(&spec, &expanded, false, out);
+                if stop {
+                    return Err(());
+                }
+                continue;
+            }
+            'q' => shell_quote(arg.unwrap_or("")),
+            'c' => arg
+                .and_then(|s| s.chars().next())
+                .map(String::from)
+                .unwrap_or_default(),
+            'd' | 'i' => {
+                let value = parse_integer(arg.unwrap_or("0"), ok);
+                let mut digits = value.unsigned_abs().to_string();
+                apply_int_precision(&spec, &mut digits);
+                let sign = if value < 0 {
+                    "-"
+                } else if spec.plus {
+                    "+"
+                } else if spec.space {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{}{}", sign, digits)
+            }
+            'u' | 'x' | 'X' | 'o' => {
+                let value = parse_integer(arg.unwrap_or("0"), ok) as u64;
+                let mut digits = match conversion {
+                    'x' => format!("{:x}", value),
+                    'X' => format!("{:X}", value),
+                    'o' => format!("{:o}", value),
+                    _ => value.to_string(),
+                };
+                apply_int_precision(&spec, &mut digits);
+                match conversion {
+                    'x' if spec.alt && value != 0 => format!("0x{}", digits),
+                    'X' if spec.alt && value != 0 => format!("0X{}", digits),
+                    'o' if spec.alt && !digits.starts_with('0') => format!("0{}", digits),
+                    _ => digits,
+                }
+            }
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => {
+                let value = parse_float(arg.unwrap_or("0"), ok);
+                let mut text = format_float(value, conversion, &spec);
+                if value >= 0.0 && !text.starts_with('-') {
+                    if spec.plus {
+                        text.insert(0, '+');
+                    } else if spec.space {
+                        text.insert(0, ' ');
+                    }
+                }
+                text
+            }
+            other => {
+                eprintln!("printf: %{}: invalid format character", other);
+                *ok = false;
+                return Ok(());
+            }
+        };
+
+        // A precision turns off zero padding for integers only, as in C.
+        let zero_fill = spec.zero
+            && match conversion {
+                's' | 'q' | 'c' => false,
+                'f' | 'F' | 'e' | 'E' | 'g' | 'G' => true,
+                _ => spec.precision.is_none(),
+            };
+        pad(&spec, text.as_bytes(), zero_fill, out);
+    }
+
+    if interpret_escapes(&literal, false, out) {
+        return Err(());
+    }
+    Ok(())
+}
+
+fn read_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits.parse().ok()
+}
+
+fn apply_int_precision(spec: &Spec, digits: &mut String) {
+    if let Some(p) = spec.precision {
+        if p == 0 && digits == "0" {
+            digits.clear();
+        }
+        while digits.len() < p {
+            digits.insert(0, '0');
+        }
+    }
+}
+
+fn pad(spec: &Spec, text: &[u8], zero_fill: bool, out: &mut Vec<u8>) {
+    let len = String::from_utf8_lossy(text).chars().count();
+    let width = spec.width.unwrap_or(0);
+    if len >= width {
+        out.extend_from_slice(text);
+        return;
+    }
+
+    let fill = width - len;
+    if spec.left {
+        out.extend_from_slice(text);
+        out.extend(std::iter::repeat_n(b' ', fill));
+    } else if zero_fill {
+        let sign_len = text
+            .iter()
+            .position(|b| !matches!(b, b'-' | b'+' | b' '))
+            .unwrap_or(0);
+        let prefix_len = match &text[sign_len..] {
+            [b'0', b'x' | b'X', ..] => sign_len + 2,
+            _ => sign_len,
+        };
+        out.extend_from_slice(&text[..prefix_len]);
+        out.extend(std::iter::repeat_n(b'0', fill));
+        out.extend_from_slice(&text[prefix_len..]);
+    } else {
+        out.extend(std::iter::repeat_n(b' ', fill));
+        out.extend_from_slice(text);
+    }
+}
+
+fn parse_integer(arg: &str, ok: &mut bool) -> i64 {
+    let trimmed = arg.trim();
+    if let Some(quoted) = trimmed.strip_prefix(['\'', '"']) {
+        return quoted.chars().next().map(|c| c as i64).unwrap_or(0);
+    }
+
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(d) => (true, d),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let parsed = if let Some(hex) = digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse()
+    };
+
+    match parsed {
+        Ok(value) if negative => -value,
+        Ok(value) => value,
+        Err(_) if trimmed.is_empty() => 0,
+        Err(_) => {
+            eprintln!("printf: {}: invalid number", arg);
+            *ok = false;
+            0
+        }
+    }
+}
+
+fn parse_float(arg: &str, ok: &mut bool) -> f64 {
+    let trimmed = arg.trim();
+    if let Some(quoted) = trimmed.strip_prefix(['\'', '"']) {
+        return quoted
+            .chars()
+            .next()
+            .map(|c| c as u32 as f64)
+            .unwrap_or(0.0);
+    }
+    match trimmed.parse() {
+        Ok(value) => value,
+        Err(_) if trimmed.is_empty() => 0.0,
+        Err(_) => {
+            eprintln!("printf: {}: invalid number", arg);
+            *ok = false;
+            0.0
+        }
+    }
+}
+
+fn format_float(value: f64, conversion: char, spec: &Spec) -> String {
+    if !value.is_finite() {
+        let text = if value.is_nan() {
+            "nan".to_string()
+        } else if value > 0.0 {
+            "inf".to_string()
+        } else {
+            "-inf".to_string()
+        };
+        return if conversion.is_ascii_uppercase() {
+            text.to_uppercase()
+        } else {
+            text
+        };
+    }
+
+    let precision = spec.precision.unwrap_or(6);
+    match conversion {
+        'f' | 'F' => format!("{:.*}", precision, value),
+        'e' | 'E' => format_exponent(value, precision, conversion == 'E'),
+        _ => {
+            let precision = precision.max(1);
+            let exponent = if value == 0.0 {
+                0
+            } else {
+                value.abs().log10().floor() as i32
+            };
+            let mut text = if exponent < -4 || exponent >= precision as i32 {
+                format_exponent(value, precision - 1, conversion == 'G')
+            } else {
+                format!(
+                    "{:.*}",
+                    (precision as i32 - 1 - exponent).max(0) as usize,
+                    value
+                )
+            };
+            if !spec.alt {
+                text = strip_trailing_zeros(&text);
+            }
+            text
+        }
+    }
+}
+
+fn format_exponent(value: f64, precision: usize, upper: bool) -> String {
+    let formatted = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let sign = if exponent < 0 { '-' } else { '+' };
+    let e = if upper { 'E' } else { 'e' };
+    format!("{}{}{}{:02}", mantissa, e, sign, exponent.abs())
+}
+
+fn strip_trailing_zeros(text: &str) -> String {
+    let (number, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], &text[i..]),
+        None => (text, ""),
+    };
+    let number = if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    };
+    format!("{}{}", number, exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printf_output(format: &str, args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        String::from_utf8(render(format, &args).0).unwrap()
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(printf_output("%s-%d-%i", &["a", "42", "-7"]), "a-42--7");
+        assert_eq!(
+            printf_output("%x %X %o %#x %#o", &["255", "255", "8", "255", "8"]),
+            "ff FF 10 0xff 010"
+        );
+        assert_eq!(printf_output("%c%c", &["hello", ""]), "h");
+        assert_eq!(printf_output("%d %d", &["0x10", "'A"]), "16 65");
+        assert_eq!(
+            printf_output("%.2f %e %g", &["3.14159", "1234.5", "0.0001"]),
+            "3.14 1.234500e+03 0.0001"
+        );
+        assert_eq!(printf_output("%q", &["a b"]), "'a b'");
+        assert_eq!(printf_output("100%%", &[]), "100%");
+    }
+
+    #[test]
+    fn padding_and_precision() {
+        assert_eq!(
+            printf_output("[%5s][%-5s]", &["ab", "ab"]),
+            "[   ab][ab   ]"
+        );
+        assert_eq!(
+            printf_output("[%05d][%+d][% d]", &["-42", "3", "3"]),
+            "[-0042][+3][ 3]"
+        );
+        assert_eq!(printf_output("[%.3d][%.0d]", &["7", "0"]), "[007][]");
+        assert_eq!(
+            printf_output("[%.2s][%*d][%-*d]", &["abc", "4", "1", "3", "2"]),
+            "[ab][   1][2  ]"
+        );
+        assert_eq!(printf_output("[%08.3f]", &["-1.5"]), "[-001.500]");
+    }
+
+    #[test]
+    fn format_is_reused_for_extra_arguments() {
+        assert_eq!(printf_output("%s=%s\n", &["a", "1", "b"]), "a=1\nb=\n");
+        assert_eq!(printf_output("x\n", &["unused"]), "x\n");
+    }
+
+    #[test]
+    fn backslash_c_stops_output() {
+        assert_eq!(printf_output("a\\cb", &[]), "a");
+        assert_eq!(printf_output("%s\\c%s", &["x", "y"]), "x");
+        assert_eq!(printf_output("%b|%s\n", &["a\\cb", "z"]), "a");
+        assert_eq!(printf_output("%b\\101\n", &["\\0101"]), "AA\n");
+    }
+
+    #[test]
+    fn invalid_numbers_fail() {
+        let args = ["12abc".to_string()];
+        assert_eq!(render("%d", &args), (b"0".to_vec(), false));
+    }
+}
@@ -0,0 +1,44 @@
+use crate::commands::complete::BuiltinSpec;
+use crate::commands::pwd_state::PwdState;
+use std::env;
+
+pub const PWD_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-L", "-P"],
+    actions: &[],
+};
+
+pub fn pwd(args: Vec<String>, pwd_state: &PwdState) -> bool {
+    let mut physical = false;
+    for arg in args
+        .iter()
+        .filter(|arg| arg.len() > 1 && arg.starts_with('-'))
+    {
+        for flag in arg[1..].chars() {
+            match flag {
+                'L' => physical = false,
+                'P' => physical = true,
+                _ => {
+                    eprintln!("pwd: -{}: invalid option", flag);
+                    eprintln!("pwd: usage: pwd [-LP]");
+                    return false;
+                }
+            }
+        }
+    }
+
+    let dir = if physical {
+        match env::current_dir() {
+            Ok(dir) => dir.display().to_string(),
+            Err(_) => {
+                eprintln!(
+                    "pwd: error retrieving current directory: getcwd: cannot access parent directories: No such file or directory"
+                );
+                return false;
+            }
+        }
+    } else {
+        pwd_state.get_current_dir()
+    };
+    println!("{}", dir.replace('\n', "\\n"));
+    true
+}
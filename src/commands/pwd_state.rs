@@ -1,7 +1,13 @@
+use crate::helpers::expand::set_variable;
+use std::env;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+
 #[derive(Debug, Clone)]
 pub struct PwdState {
     current_dir: String,
     old_dir: String,
+    stack: Vec<String>,
 }
 
 impl PwdState {
@@ -9,9 +15,12 @@ impl PwdState {
         Self {
             current_dir,
             old_dir,
+            stack: Vec::new(),
         }
     }
     pub fn set_states(&mut self, new_current: String, new_old: String) {
+        set_variable("PWD", &new_current);
+        set_variable("OLDPWD", &new_old);
         self.current_dir = new_current;
         self.old_dir = new_old;
     }
@@ -23,4 +32,124 @@ impl PwdState {
     pub fn get_old_dir(&self) -> String {
         self.old_dir.clone()
     }
+
+    // The directory stack as `dirs` shows it: the current directory first, then the
+    // directories saved by pushd.
+    pub fn get_dirs(&self) -> Vec<String> {
+        let mut dirs = vec![self.current_dir.clone()];
+        dirs.extend(self.stack.iter().cloned());
+        dirs
+    }
+
+    pub fn stack_mut(&mut self) -> &mut Vec<String> {
+        &mut self.stack
+    }
+
+    // Resolves `N` / `+N` counted from the left of `dirs` and `-N` from the right.
+    pub fn stack_index(&self, spec: &str) -> Option<usize> {
+        let len = self.stack.len() + 1;
+        if let Some(n) = spec.strip_prefix('-') {
+            let n: usize = n.parse().ok()?;
+            return len.checked_sub(n.checked_add(1)?);
+        }
+        let n: usize = spec.strip_prefix('+').unwrap_or(spec).parse().ok()?;
+        (n < len).then_some(n)
+    }
+
+    // `dirs` turned so that entry `index` comes first, as `pushd +N` leaves it.
+    pub fn rotated(&self, index: usize) -> Vec<String> {
+        let mut dirs = self.get_dirs();
+        dirs.rotate_left(index);
+        dirs
+    }
+}
+
+// Joins `target` onto the logical directory and folds `.` and `..` away by name, so `..`
+// leaves a symlinked directory the way it was entered.
+pub fn logical_path(base: &str, target: &Path) -> PathBuf {
+    let mut path = PathBuf::from("/");
+    for component in Path::new(base).join(target).components() {
+        match component {
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::Normal(name) => path.push(name),
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    path
+}
+
+// Keeps an inherited $PWD while it still names the directory the shell started in, so a
+// path entered through symlinks survives; otherwise falls back to the real path.
+pub fn initial_dir() -> String {
+    let here = Path::new(".").metadata().ok();
+    if let Ok(pwd) = env::var("PWD")
+        && logical_path("/", Path::new(&pwd)) == Path::new(&pwd)
+        && let Ok(there) = Path::new(&pwd).metadata()
+        && here.is_some_and(|here| here.dev() == there.dev() && here.ino() == there.ino())
+    {
+        return pwd;
+    }
+    match env::current_dir() {
+        Ok(dir) => dir.display().to_string(),
+        Err(_) => env::var("PWD").unwrap_or_else(|_| "/".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parent_segments_fold_by_name() {
+        assert_eq!(logical_path("/a/b", Path::new("../c")), Path::new("/a/c"));
+        assert_eq!(logical_path("/a/b", Path::new("../../..")), Path::new("/"));
+        assert_eq!(logical_path("/", Path::new("..")), Path::new("/"));
+    }
+
+    #[test]
+    fn current_segments_are_dropped() {
+        assert_eq!(
+            logical_path("/a", Path::new("./b/./c/.")),
+            Path::new("/a/b/c")
+        );
+        assert_eq!(logical_path("/a/./b", Path::new(".")), Path::new("/a/b"));
+    }
+
+    fn stacked(dirs: &[&str]) -> PwdState {
+        let mut state = PwdState::new(dirs[0].to_string(), "/".to_string());
+        state
+            .stack_mut()
+            .extend(dirs[1..].iter().map(|dir| dir.to_string()));
+        state
+    }
+
+    #[test]
+    fn stack_indexes_count_from_either_end() {
+        let state = stacked(&["/a", "/b", "/c"]);
+        assert_eq!(state.stack_index("0"), Some(0));
+        assert_eq!(state.stack_index("+2"), Some(2));
+        assert_eq!(state.stack_index("-0"), Some(2));
+        assert_eq!(state.stack_index("-2"), Some(0));
+        assert_eq!(state.stack_index("3"), None);
+        assert_eq!(state.stack_index("-3"), None);
+        assert_eq!(state.stack_index("-18446744073709551615"), None);
+        assert_eq!(state.stack_index("+99999999999999999999"), None);
+    }
+
+    #[test]
+    fn rotation_keeps_the_ring_order() {
+        let state = stacked(&["/a", "/b", "/c"]);
+        assert_eq!(state.rotated(1), ["/b", "/c", "/a"]);
+        assert_eq!(
+            state.rotated(state.stack_index("-0").unwrap()),
+            ["/c", "/a", "/b"]
+        );
+    }
+
+    #[test]
+    fn absolute_targets_ignore_the_base() {
+        assert_eq!(logical_path("/a/b", Path::new("/x/../y")), Path::new("/y"));
+    }
 }
@@ -0,0 +1,352 @@
+use crate::commands::complete::{Action, BuiltinSpec};
+use crate::helpers::expand::{is_name, set_variable};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+pub const READ_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-d", "-n", "-p", "-r", "-s", "-t"],
+    actions: &[Action::Variable],
+};
+
+struct ReadOptions {
+    prompt: Option<String>,
+    silent: bool,
+    raw: bool,
+    timeout: Option<Duration>,
+    nchars: Option<usize>,
+    delimiter: char,
+    names: Vec<String>,
+}
+
+enum ReadEnd {
+    Delimiter,
+    Eof,
+    Timeout,
+    Interrupted,
+}
+
+pub fn read(args: Vec<String>) -> bool {
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("read: {}", msg);
+            eprintln!(
+                "read: usage: read [-rs] [-d delim] [-n nchars] [-p prompt] [-t timeout] [name ...]"
+            );
+            return false;
+        }
+    };
+
+    for name in &options.names {
+        if !is_name(name) {
+            eprintln!("read: `{}': not a valid identifier", name);
+            return false;
+        }
+    }
+
+    let (line, end) = if io::stdin().is_terminal() {
+        if let Some(prompt) = &options.prompt {
+            eprint!("{}", prompt);
+            io::stderr().flush().ok();
+        }
+        match read_terminal(&options) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("read: {}", e);
+                return false;
+            }
+        }
+    } else {
+        read_stdin(&options)
+    };
+
+    if matches!(end, ReadEnd::Timeout | ReadEnd::Interrupted) {
+        return false;
+    }
+
+    if options.names.is_empty() {
+        let value = if options.raw {
+            line
+        } else {
+            remove_escapes(&line)
+        };
+        set_variable("REPLY", &value);
+    } else {
+        let fields = split_fields(&line, options.names.len(), options.raw);
+        for (name, value) in options.names.iter().zip(fields) {
+            set_variable(name, &value);
+        }
+    }
+
+    !matches!(end, ReadEnd::Eof)
+}
+
+fn parse_options(args: Vec<String>) -> Result<ReadOptions, String> {
+    let mut options = ReadOptions {
+        prompt: None,
+        silent: false,
+        raw: false,
+        timeout: None,
+        nchars: None,
+        delimiter: '\n',
+        names: Vec::new(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            options.names.extend(args.by_ref());
+            break;
+        }
+        if !arg.starts_with('-') || arg.len() == 1 {
+            options.names.push(arg);
+            options.names.extend(args.by_ref());
+            break;
+        }
+
+        let mut flags = arg[1..].chars();
+        while let Some(flag) = flags.next() {
+            match flag {
+                'r' => options.raw = true,
+                's' => options.silent = true,
+                'p' | 't' | 'n' | 'd' => {
+                    let rest: String = flags.by_ref().collect();
+                    let value = if rest.is_empty() {
+                        args.next()
+                            .ok_or_else(|| format!("-{}: option requires an argument", flag))?
+                    } else {
+                        rest
+                    };
+                    match flag {
+                        'p' => options.prompt = Some(value),
+                        't' => {
+                            let secs: f64 =
+                                value.parse().ok().filter(|s: &f64| *s >= 0.0).ok_or_else(
+                                    || format!("{}: invalid timeout specification", value),
+                                )?;
+                            options.timeout = Some(Duration::from_secs_f64(secs));
+                        }
+                        'n' => {
+                            let n = value
+                                .parse()
+                                .map_err(|_| format!("{}: invalid number", value))?;
+                            options.nchars = Some(n);
+                        }
+                        _ => options.delimiter = value.chars().next().unwrap_or('\0'),
+                    }
+                }
+                _ => return Err(format!("-{}: invalid option", flag)),
+            }
+        }
+    }
+    Ok(options)
+}
+
+fn read_terminal(options: &ReadOptions) -> io::Result<(String, ReadEnd)> {
+    enable_raw_mode()?;
+    let result = read_keys(options);
+    disable_raw_mode()?;
+    result
+}
+
+fn read_keys(options: &ReadOptions) -> io::Result<(String, ReadEnd)> {
+    let deadline = options.timeout.map(|t| Instant::now() + t);
+    let mut line = String::new();
+    let mut stderr = io::stderr();
+
+    let end = loop {
+        if options.nchars.is_some_and(|n| line.chars().count() >= n) {
+            break ReadEnd::Delimiter;
+        }
+
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !event::poll(remaining)? {
+                break ReadEnd::Timeout;
+            }
+        }
+
+        let Event::Key(key_event) = event::read()? else {
+            continue;
+        };
+        if key_event.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key_event.code {
+            KeyCode::Char(c) if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                if c == 'd' && line.is_empty() {
+                    break ReadEnd::Eof;
+                } else if c == 'c' {
+                    write!(stderr, "^C")?;
+                    break ReadEnd::Interrupted;
+                }
+            }
+            KeyCode::Char(c) if c == options.delimiter => break ReadEnd::Delimiter,
+            KeyCode::Char(c) => {
+                line.push(c);
+                if !options.silent {
+                    write!(stderr, "{}", c)?;
+                }
+            }
+            KeyCode::Tab => {
+                line.push('\t');
+                if !options.silent {
+                    write!(stderr, "\t")?;
+                }
+            }
+            KeyCode::Backspace if !line.is_empty() => {
+                line.pop();
+                if !options.silent {
+                    write!(stderr, "\x08 \x08")?;
+                }
+            }
+            KeyCode::Enter if options.delimiter == '\n' => {
+                if !options.raw && ends_with_escape(&line) {
+                    line.pop();
+                    if !options.silent {
+                        write!(stderr, "\r\n")?;
+                    }
+                    continue;
+                }
+                break ReadEnd::Delimiter;
+            }
+            KeyCode::Enter => {
+                line.push('\n');
+                if !options.silent {
+                    write!(stderr, "\r\n")?;
+                }
+            }
+            _ => {}
+        }
+        stderr.flush()?;
+    };
+
+    let newline = matches!(end, ReadEnd::Interrupted | ReadEnd::Eof)
+        || (matches!(end, ReadEnd::Delimiter) && !options.silent && options.nchars.is_none());
+    if newline {
+        write!(stderr, "\r\n")?;
+    }
+    stderr.flush()?;
+    Ok((line, end))
+}
+
+fn read_stdin(options: &ReadOptions) -> (String, ReadEnd) {
+    let deadline = options.timeout.map(|t| Instant::now() + t);
+    let mut bytes: Vec<u8> = Vec::new();
+
+    let end = loop {
+        if options
+            .nchars
+            .is_some_and(|n| String::from_utf8_lossy(&bytes).chars().count() >= n)
+        {
+            break ReadEnd::Delimiter;
+        }
+
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let mut fd = libc::pollfd {
+                fd: 0,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut fd, 1, remaining.as_millis() as i32) } <= 0 {
+                break ReadEnd::Timeout;
+            }
+        }
+
+        let mut byte = 0u8;
+        if unsafe { libc::read(0, (&mut byte as *mut u8).cast(), 1) } != 1 {
+            break ReadEnd::Eof;
+        }
+
+        if byte as u32 == options.delimiter as u32 {
+            let line = String::from_utf8_lossy(&bytes);
+            if options.delimiter == '\n' && !options.raw && ends_with_escape(&line) {
+                bytes.pop();
+                continue;
+            }
+            break ReadEnd::Delimiter;
+        }
+        bytes.push(byte);
+    };
+
+    (String::from_utf8_lossy(&bytes).into_owned(), end)
+}
+
+fn ends_with_escape(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+fn remove_escapes(line: &str) -> String {
+    let mut out = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn split_fields(line: &str, count: usize, raw: bool) -> Vec<String> {
+    let ifs = env::var("IFS").unwrap_or_else(|_| " \t\n".to_string());
+    let is_separator = |c: char| ifs.contains(c);
+    let is_ifs_space = |c: char| ifs.contains(c) && c.is_whitespace();
+
+    let mut chars: Vec<(char, bool)> = Vec::new();
+    let mut iter = line.chars();
+    while let Some(c) = iter.next() {
+        if c == '\\' && !raw {
+            if let Some(next) = iter.next() {
+                chars.push((next, true));
+            }
+        } else {
+            chars.push((c, false));
+        }
+    }
+
+    let mut pos = 0;
+    let skip_spaces = |pos: &mut usize| {
+        while *pos < chars.len() && !chars[*pos].1 && is_ifs_space(chars[*pos].0) {
+            *pos += 1;
+        }
+    };
+    skip_spaces(&mut pos);
+
+    let mut fields = Vec::new();
+    while fields.len() + 1 < count && pos < chars.len() {
+        let mut field = String::new();
+        while pos < chars.len() && (chars[pos].1 || !is_separator(chars[pos].0)) {
+            field.push(chars[pos].0);
+            pos += 1;
+        }
+        fields.push(field);
+
+        skip_spaces(&mut pos);
+        if pos < chars.len() && !chars[pos].1 && is_separator(chars[pos].0) {
+            pos += 1;
+            skip_spaces(&mut pos);
+        }
+    }
+
+    let mut end = chars.len();
+    while end > pos && !chars[end - 1].1 && is_ifs_space(chars[end - 1].0) {
+        end -= 1;
+    }
+    fields.push(chars[pos..end].iter().map(|(c, _)| c).collect());
+
+    fields.resize(count, String::new());
+    fields
+}
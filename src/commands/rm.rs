@@ -1,5 +1,11 @@
+use crate::commands::complete::{Action, BuiltinSpec};
 use std::path::Path;
 
+pub const RM_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-r", "-R", "--recursive"],
+    actions: &[Action::File],
+};
+
 pub fn rm(args: Vec<String>) -> bool {
     let mut recursive = false;
 
@@ -8,8 +14,8 @@ pub fn rm(args: Vec<String>) -> bool {
             recursive = true;
             continue;
         }
-        if arg.starts_with("-") {
-            for c in arg[1..].chars() {
+        if let Some(opts) = arg.strip_prefix("-") {
+            for c in opts.chars() {
                 if c != '-' && c != 'r' && c != 'R' {
                     println!("rm: invalid option -- '{}'", c);
                     return false;
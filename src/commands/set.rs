@@ -0,0 +1,74 @@
+use crate::commands::complete::BuiltinSpec;
+use crate::commands::shell_options::{ShellOption, ShellOptions};
+use crate::helpers::expand::shell_quote;
+use std::env;
+
+pub const SET_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-C", "-e", "-o", "-u", "-x"],
+    actions: &[],
+};
+
+pub fn set(args: Vec<String>, options: &mut ShellOptions) -> bool {
+    if args.is_empty() {
+        let mut vars: Vec<(String, String)> = env::vars().collect();
+        vars.sort();
+        for (name, value) in vars {
+            println!("{}={}", name, shell_quote(&value));
+        }
+        return true;
+    }
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+
+        let enable = match arg.chars().next() {
+            Some('-') => true,
+            Some('+') => false,
+            _ => {
+                eprintln!("set: {}: positional parameters are not supported", arg);
+                return false;
+            }
+        };
+        let sign = if enable { '-' } else { '+' };
+
+        for c in arg[1..].chars() {
+            if c == 'o' {
+                match args.next() {
+                    Some(name) => match ShellOption::from_name(&name) {
+                        Some(option) => options.set(option, enable),
+                        None => {
+                            eprintln!("set: {}: invalid option name", name);
+                            return false;
+                        }
+                    },
+                    None => print_options(options, enable),
+                }
+                continue;
+            }
+
+            match ShellOption::from_letter(c) {
+                Some(option) => options.set(option, enable),
+                None => {
+                    eprintln!("set: {}{}: invalid option", sign, c);
+                    eprintln!("set: usage: set [-euxC] [-o option-name]");
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+fn print_options(options: &ShellOptions, human_readable: bool) {
+    for option in ShellOption::ALL {
+        let on = options.is_set(option);
+        if human_readable {
+            println!("{:<15}\t{}", option.name(), if on { "on" } else { "off" });
+        } else {
+            println!("set {}o {}", if on { '-' } else { '+' }, option.name());
+        }
+    }
+}
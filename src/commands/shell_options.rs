@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShellOption {
+    Autocd,
+    Emacs,
+    Errexit,
+    Noclobber,
+    Nounset,
+    Pipefail,
+    Vi,
+    Xtrace,
+}
+
+impl ShellOption {
+    pub const ALL: [ShellOption; 8] = [
+        ShellOption::Autocd,
+        ShellOption::Emacs,
+        ShellOption::Errexit,
+        ShellOption::Noclobber,
+        ShellOption::Nounset,
+        ShellOption::Pipefail,
+        ShellOption::Vi,
+        ShellOption::Xtrace,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShellOption::Autocd => "autocd",
+            ShellOption::Emacs => "emacs",
+            ShellOption::Errexit => "errexit",
+            ShellOption::Noclobber => "noclobber",
+            ShellOption::Nounset => "nounset",
+            ShellOption::Pipefail => "pipefail",
+            ShellOption::Vi => "vi",
+            ShellOption::Xtrace => "xtrace",
+        }
+    }
+
+    pub fn letter(self) -> Option<char> {
+        match self {
+            ShellOption::Errexit => Some('e'),
+            ShellOption::Noclobber => Some('C'),
+            ShellOption::Nounset => Some('u'),
+            ShellOption::Autocd | ShellOption::Emacs | ShellOption::Pipefail | ShellOption::Vi => {
+                None
+            }
+            ShellOption::Xtrace => Some('x'),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ShellOption> {
+        Self::ALL.into_iter().find(|opt| opt.name() == name)
+    }
+
+    pub fn from_letter(letter: char) -> Option<ShellOption> {
+        Self::ALL
+            .into_iter()
+            .find(|opt| opt.letter() == Some(letter))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShellOptions {
+    enabled: HashSet<ShellOption>,
+}
+
+impl Default for ShellOptions {
+    fn default() -> Self {
+        Self {
+            enabled: HashSet::from([ShellOption::Emacs]),
+        }
+    }
+}
+
+impl ShellOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_set(&self, option: ShellOption) -> bool {
+        self.enabled.contains(&option)
+    }
+
+    pub fn set(&mut self, option: ShellOption, on: bool) {
+        if on {
+            match option {
+                ShellOption::Emacs => self.enabled.remove(&ShellOption::Vi),
+                ShellOption::Vi => self.enabled.remove(&ShellOption::Emacs),
+                _ => false,
+            };
+            self.enabled.insert(option);
+        } else {
+            self.enabled.remove(&option);
+        }
+    }
+}
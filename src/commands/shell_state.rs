@@ -0,0 +1,37 @@
+use crate::commands::alias::Aliases;
+use crate::commands::complete::CompletionSpecs;
+use crate::commands::hash::HashTable;
+use crate::commands::history::History;
+use crate::commands::pwd_state::PwdState;
+use crate::commands::shell_options::ShellOptions;
+use crate::helpers::git_prompt::GitCache;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct ShellState {
+    pub pwd: PwdState,
+    pub options: ShellOptions,
+    pub last_status: i32,
+    pub last_duration: Duration,
+    pub aliases: Aliases,
+    pub hash: HashTable,
+    pub history: History,
+    pub completions: CompletionSpecs,
+    pub git: GitCache,
+}
+
+impl ShellState {
+    pub fn new(pwd: PwdState) -> Self {
+        Self {
+            pwd,
+            options: ShellOptions::new(),
+            last_status: 0,
+            last_duration: Duration::ZERO,
+            aliases: Aliases::new(),
+            hash: HashTable::new(),
+            history: History::new(),
+            completions: CompletionSpecs::new(),
+            git: GitCache::new(),
+        }
+    }
+}
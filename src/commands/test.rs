@@ -0,0 +1,488 @@
+use crate::commands::complete::{Action, BuiltinSpec};
+use crate::commands::shell_options::ShellOption;
+use crate::commands::shell_state::ShellState;
+use crate::helpers::expand::{expand_pattern, expand_regex, expand_word};
+use std::ffi::CString;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::{env, fs};
+
+pub const TEST_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &[],
+    actions: &[Action::File],
+};
+
+const UNARY_OPS: &[&str] = &[
+    "-b", "-c", "-d", "-e", "-f", "-g", "-G", "-h", "-k", "-L", "-n", "-N", "-O", "-p", "-r", "-s",
+    "-S", "-t", "-u", "-w", "-x", "-z",
+];
+
+const BINARY_OPS: &[&str] = &[
+    "=", "==", "!=", "<", ">", "-eq", "-ne", "-lt", "-le", "-gt", "-ge", "-nt", "-ot", "-ef",
+];
+
+pub fn test(mut args: Vec<String>, bracket: bool) -> i32 {
+    let name = if bracket { "[" } else { "test" };
+    if bracket {
+        if args.last().map(String::as_str) != Some("]") {
+            eprintln!("[: missing `]'");
+            return 2;
+        }
+        args.pop();
+    }
+
+    match evaluate(&args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(msg) => {
+            eprintln!("{}: {}", name, msg);
+            2
+        }
+    }
+}
+
+fn evaluate(args: &[String]) -> Result<bool, String> {
+    match args.len() {
+        0 => Ok(false),
+        1 => Ok(!args[0].is_empty()),
+        2 if args[0] == "!" => Ok(args[1].is_empty()),
+        2 if UNARY_OPS.contains(&args[0].as_str()) => unary_test(&args[0], &args[1]),
+        2 => Err(format!("{}: unary operator expected", args[0])),
+        3 if BINARY_OPS.contains(&args[1].as_str()) => binary_test(&args[0], &args[1], &args[2]),
+        3 if args[1] == "-a" => Ok(!args[0].is_empty() && !args[2].is_empty()),
+        3 if args[1] == "-o" => Ok(!args[0].is_empty() || !args[2].is_empty()),
+        3 if args[0] == "!" => evaluate(&args[1..]).map(|r| !r),
+        3 if args[0] == "(" && args[2] == ")" => Ok(!args[1].is_empty()),
+        3 => Err(format!("{}: binary operator expected", args[1])),
+        4 if args[0] == "!" => evaluate(&args[1..]).map(|r| !r),
+        4 if args[0] == "(" && args[3] == ")" => evaluate(&args[1..3]),
+        _ => {
+            let mut parser = TestParser { args, pos: 0 };
+            let result = parser.or()?;
+            if parser.pos < args.len() {
+                return Err("too many arguments".to_string());
+            }
+            Ok(result)
+        }
+    }
+}
+
+struct TestParser<'a> {
+    args: &'a [String],
+    pos: usize,
+}
+
+impl<'a> TestParser<'a> {
+    fn peek(&self, offset: usize) -> Option<&'a str> {
+        self.args.get(self.pos + offset).map(String::as_str)
+    }
+
+    fn or(&mut self) -> Result<bool, String> {
+        let mut result = self.and()?;
+        while self.peek(0) == Some("-o") {
+            self.pos += 1;
+            result |= self.and()?;
+        }
+        Ok(result)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut result = self.not()?;
+        while self.peek(0) == Some("-a") {
+            self.pos += 1;
+            result &= self.not()?;
+        }
+        Ok(result)
+    }
+
+    fn not(&mut self) -> Result<bool, String> {
+        if self.peek(0) == Some("!") {
+            self.pos += 1;
+            return self.not().map(|r| !r);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<bool, String> {
+        let Some(first) = self.peek(0) else {
+            return Err("argument expected".to_string());
+        };
+
+        if first == "(" {
+            self.pos += 1;
+            let result = self.or()?;
+            if self.peek(0) != Some(")") {
+                return Err("`)' expected".to_string());
+            }
+            self.pos += 1;
+            return Ok(result);
+        }
+
+        if let Some(op) = self.peek(1)
+            && BINARY_OPS.contains(&op)
+            && let Some(rhs) = self.peek(2)
+        {
+            let result = binary_test(first, op, rhs);
+            self.pos += 3;
+            return result;
+        }
+
+        if UNARY_OPS.contains(&first)
+            && let Some(operand) = self.peek(1)
+        {
+            let result = unary_test(first, operand);
+            self.pos += 2;
+            return result;
+        }
+
+        self.pos += 1;
+        Ok(!first.is_empty())
+    }
+}
+
+pub fn conditional(words: &[String], state: &ShellState) -> i32 {
+    let Some((last, inner)) = words.split_last() else {
+        eprintln!("0-shell: syntax error: expected `]]'");
+        return 2;
+    };
+    if last != "]]" {
+        eprintln!("0-shell: syntax error near `{}': expected `]]'", last);
+        return 2;
+    }
+
+    let mut parser = ConditionalParser {
+        words: inner,
+        pos: 0,
+        state,
+        evaluate: true,
+    };
+    let result = parser.or().and_then(|result| match parser.peek(0) {
+        Some(word) => Err(format!(
+            "syntax error in conditional expression near `{}'",
+            word
+        )),
+        None => Ok(result),
+    });
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(msg) => {
+            eprintln!("0-shell: {}", msg);
+            2
+        }
+    }
+}
+
+// `evaluate` is cleared while parsing the side of `&&`/`||` that the left side already
+// decided, so it is checked for syntax without expanding words or touching files.
+struct ConditionalParser<'a> {
+    words: &'a [String],
+    pos: usize,
+    state: &'a ShellState,
+    evaluate: bool,
+}
+
+impl<'a> ConditionalParser<'a> {
+    fn peek(&self, offset: usize) -> Option<&'a str> {
+        self.words.get(self.pos + offset).map(String::as_str)
+    }
+
+    fn operand(&mut self) -> Result<String, String> {
+        let Some(raw) = self.peek(0) else {
+            return Err("unexpected argument `]]' to conditional operator".to_string());
+        };
+        self.pos += 1;
+        if !self.evaluate {
+            return Ok(String::new());
+        }
+        expand_word(raw, self.state)
+    }
+
+    fn unless(
+        &mut self,
+        decided: bool,
+        next: fn(&mut Self) -> Result<bool, String>,
+    ) -> Result<bool, String> {
+        let evaluate = self.evaluate;
+        self.evaluate = evaluate && !decided;
+        let result = next(self);
+        self.evaluate = evaluate;
+        result
+    }
+
+    fn or(&mut self) -> Result<bool, String> {
+        let mut result = self.and()?;
+        while self.peek(0) == Some("||") {
+            self.pos += 1;
+            let rhs = self.unless(result, Self::and)?;
+            result = result || rhs;
+        }
+        Ok(result)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut result = self.not()?;
+        while self.peek(0) == Some("&&") {
+            self.pos += 1;
+            let rhs = self.unless(!result, Self::not)?;
+            result = result && rhs;
+        }
+        Ok(result)
+    }
+
+    fn not(&mut self) -> Result<bool, String> {
+        if self.peek(0) == Some("!") {
+            self.pos += 1;
+            return self.not().map(|r| !r);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<bool, String> {
+        let Some(first) = self.peek(0) else {
+            return Err("unexpected end of conditional expression".to_string());
+        };
+
+        if first == "(" {
+            self.pos += 1;
+            let result = self.or()?;
+            if self.peek(0) != Some(")") {
+                return Err("expected `)' in conditional expression".to_string());
+            }
+            self.pos += 1;
+            return Ok(result);
+        }
+
+        if let Some(op) = self.peek(1)
+            && (BINARY_OPS.contains(&op) || op == "=~")
+        {
+            let op = op.to_string();
+            let lhs = self.operand()?;
+            self.pos += 1;
+            let Some(raw_rhs) = self.peek(0) else {
+                return Err(format!(
+                    "unexpected argument to conditional binary operator `{}'",
+                    op
+                ));
+            };
+            self.pos += 1;
+            if !self.evaluate {
+                return Ok(false);
+            }
+            return match op.as_str() {
+                "==" | "=" => Ok(pattern_match(&lhs, &expand_pattern(raw_rhs, self.state)?)),
+                "!=" => Ok(!pattern_match(&lhs, &expand_pattern(raw_rhs, self.state)?)),
+                "=~" => regex_match(&lhs, &expand_regex(raw_rhs, self.state)?),
+                _ => binary_test(&lhs, &op, &expand_word(raw_rhs, self.state)?),
+            };
+        }
+
+        if (UNARY_OPS.contains(&first) || first == "-v" || first == "-o") && self.peek(1).is_some()
+        {
+            let op = first.to_string();
+            self.pos += 1;
+            let operand = self.operand()?;
+            if !self.evaluate {
+                return Ok(false);
+            }
+            return match op.as_str() {
+                "-v" => Ok(env::var_os(&operand).is_some()),
+                "-o" => match ShellOption::from_name(&operand) {
+                    Some(option) => Ok(self.state.options.is_set(option)),
+                    None => Ok(false),
+                },
+                _ => unary_test(&op, &operand),
+            };
+        }
+
+        Ok(!self.operand()?.is_empty())
+    }
+}
+
+fn unary_test(op: &str, operand: &str) -> Result<bool, String> {
+    if op == "-n" {
+        return Ok(!operand.is_empty());
+    }
+    if op == "-z" {
+        return Ok(operand.is_empty());
+    }
+    if op == "-t" {
+        let fd = parse_integer(operand)? as i32;
+        return Ok(unsafe { libc::isatty(fd) } == 1);
+    }
+
+    if op == "-h" || op == "-L" {
+        return Ok(fs::symlink_metadata(operand).is_ok_and(|m| m.file_type().is_symlink()));
+    }
+
+    let Ok(metadata) = fs::metadata(operand) else {
+        return Ok(false);
+    };
+    let file_type = metadata.file_type();
+    let mode = metadata.permissions().mode();
+
+    Ok(match op {
+        "-e" => true,
+        "-f" => file_type.is_file(),
+        "-d" => file_type.is_dir(),
+        "-b" => file_type.is_block_device(),
+        "-c" => file_type.is_char_device(),
+        "-p" => file_type.is_fifo(),
+        "-S" => file_type.is_socket(),
+        "-s" => metadata.len() > 0,
+        "-g" => mode & 0o2000 != 0,
+        "-u" => mode & 0o4000 != 0,
+        "-k" => mode & 0o1000 != 0,
+        "-r" => has_access(operand, libc::R_OK),
+        "-w" => has_access(operand, libc::W_OK),
+        "-x" => has_access(operand, libc::X_OK),
+        "-O" => metadata.uid() == unsafe { libc::geteuid() },
+        "-G" => metadata.gid() == unsafe { libc::getegid() },
+        "-N" => metadata.mtime() > metadata.atime(),
+        _ => return Err(format!("{}: unary operator expected", op)),
+    })
+}
+
+// Asks the kernel with the effective ids, so supplementary groups, root and ACLs are
+// all accounted for.
+fn has_access(path: &str, mode: libc::c_int) -> bool {
+    let Ok(path) = CString::new(path) else {
+        return false;
+    };
+    unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+fn binary_test(lhs: &str, op: &str, rhs: &str) -> Result<bool, String> {
+    Ok(match op {
+        "=" | "==" => lhs == rhs,
+        "!=" => lhs != rhs,
+        "<" => lhs < rhs,
+        ">" => lhs > rhs,
+        "-eq" => parse_integer(lhs)? == parse_integer(rhs)?,
+        "-ne" => parse_integer(lhs)? != parse_integer(rhs)?,
+        "-lt" => parse_integer(lhs)? < parse_integer(rhs)?,
+        "-le" => parse_integer(lhs)? <= parse_integer(rhs)?,
+        "-gt" => parse_integer(lhs)? > parse_integer(rhs)?,
+        "-ge" => parse_integer(lhs)? >= parse_integer(rhs)?,
+        "-nt" | "-ot" => {
+            let modified = |path: &str| fs::metadata(path).and_then(|m| m.modified()).ok();
+            match (modified(lhs), modified(rhs)) {
+                (Some(a), Some(b)) if op == "-nt" => a > b,
+                (Some(a), Some(b)) => a < b,
+                (Some(_), None) => op == "-nt",
+                (None, Some(_)) => op == "-ot",
+                (None, None) => false,
+            }
+        }
+        "-ef" => match (fs::metadata(lhs), fs::metadata(rhs)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        },
+        _ => return Err(format!("{}: binary operator expected", op)),
+    })
+}
+
+fn parse_integer(value: &str) -> Result<i64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{}: integer expression expected", value))
+}
+
+fn pattern_match(text: &str, pattern: &str) -> bool {
+    let (Ok(text), Ok(pattern)) = (CString::new(text), CString::new(pattern)) else {
+        return false;
+    };
+    unsafe { libc::fnmatch(pattern.as_ptr(), text.as_ptr(), 0) == 0 }
+}
+
+fn regex_match(text: &str, pattern: &str) -> Result<bool, String> {
+    let (Ok(c_text), Ok(c_pattern)) = (CString::new(text), CString::new(pattern)) else {
+        return Ok(false);
+    };
+    unsafe {
+        let mut regex: libc::regex_t = std::mem::zeroed();
+        if libc::regcomp(
+            &mut regex,
+            c_pattern.as_ptr(),
+            libc::REG_EXTENDED | libc::REG_NOSUB,
+        ) != 0
+        {
+            return Err(format!("{}: invalid regular expression", pattern));
+        }
+        let matched = libc::regexec(&regex, c_text.as_ptr(), 0, std::ptr::null_mut(), 0) == 0;
+        libc::regfree(&mut regex);
+        Ok(matched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pwd_state::PwdState;
+
+    fn run(args: &[&str]) -> i32 {
+        test(args.iter().map(|arg| arg.to_string()).collect(), false)
+    }
+
+    #[test]
+    fn compares_strings_and_integers() {
+        assert_eq!(run(&["abc", "=", "abc"]), 0);
+        assert_eq!(run(&["abc", "!=", "abc"]), 1);
+        assert_eq!(run(&["b", ">", "a"]), 0);
+        assert_eq!(run(&["-n", ""]), 1);
+        assert_eq!(run(&["-z", ""]), 0);
+        assert_eq!(run(&["10", "-gt", "9"]), 0);
+        assert_eq!(run(&[" 3", "-le", "3"]), 0);
+        assert_eq!(run(&["-5", "-ge", "2"]), 1);
+    }
+
+    #[test]
+    fn bad_integers_exit_with_two() {
+        assert_eq!(run(&["1", "-eq", "x"]), 2);
+        assert_eq!(run(&["", "-lt", "1"]), 2);
+        assert_eq!(run(&["a", "b"]), 2);
+        assert_eq!(test(vec!["1".to_string()], true), 2);
+    }
+
+    #[test]
+    fn argument_counts_follow_posix() {
+        assert_eq!(run(&[]), 1);
+        assert_eq!(run(&["-n"]), 0);
+        assert_eq!(run(&[""]), 1);
+        assert_eq!(run(&["!", ""]), 0);
+        assert_eq!(run(&["-n", "=", "-n"]), 0);
+        assert_eq!(run(&["!", "-z", "x"]), 0);
+        assert_eq!(run(&["(", "", ")"]), 1);
+        assert_eq!(run(&["!", "-n", "=", "-n"]), 1);
+        assert_eq!(run(&["(", "-z", "", ")"]), 0);
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        assert_eq!(run(&["x", "-o", "", "-a", ""]), 0);
+        assert_eq!(run(&["(", "x", "-o", "", ")", "-a", ""]), 1);
+        assert_eq!(run(&["!", "a", "=", "a", "-o", "b", "=", "b"]), 0);
+        assert_eq!(run(&["!", "(", "a", "=", "a", "-o", "b", "=", "b", ")"]), 1);
+        assert_eq!(run(&["x", "-a", "x", "x"]), 2);
+    }
+
+    #[test]
+    fn file_operators() {
+        assert_eq!(run(&["-d", "/"]), 0);
+        assert_eq!(run(&["-f", "/"]), 1);
+        assert_eq!(run(&["-e", "/nonexistent/path"]), 1);
+        assert_eq!(run(&["-x", "/nonexistent/path"]), 1);
+        assert_eq!(run(&["/", "-ef", "/."]), 0);
+    }
+
+    #[test]
+    fn conditional_skips_the_decided_side() {
+        let state = ShellState::new(PwdState::new("/".to_string(), "/".to_string()));
+        let words = |text: &str| -> Vec<String> { text.split(' ').map(str::to_string).collect() };
+        assert_eq!(conditional(&words("'' && 1 -gt x ]]"), &state), 1);
+        assert_eq!(conditional(&words("x || 1 -gt x ]]"), &state), 0);
+        assert_eq!(conditional(&words("x && 1 -gt x ]]"), &state), 2);
+        assert_eq!(conditional(&words("( x ]]"), &state), 2);
+    }
+}
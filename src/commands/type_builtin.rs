@@ -0,0 +1,264 @@
+use crate::commands::alias::format_alias;
+use crate::commands::complete::{Action, BuiltinSpec};
+use crate::commands::hash::{is_executable, search_path, search_path_all};
+use crate::commands::shell_state::ShellState;
+use crate::helpers::parser::{BUILTINS, KEYWORDS};
+use std::path::{Path, PathBuf};
+
+pub const TYPE_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-a", "-f", "-P", "-p", "-t"],
+    actions: &[Action::Command],
+};
+
+pub const WHICH_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-a"],
+    actions: &[Action::Command],
+};
+
+pub const COMMAND_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-p", "-V", "-v"],
+    actions: &[Action::Command],
+};
+
+pub enum Resolution {
+    Alias(String),
+    Keyword,
+    Builtin,
+    File(PathBuf, bool),
+}
+
+pub fn resolve(name: &str, state: &ShellState, all: bool, path_only: bool) -> Vec<Resolution> {
+    let mut found = Vec::new();
+
+    if !path_only {
+        if let Some(value) = state.aliases.get(name) {
+            found.push(Resolution::Alias(value.to_string()));
+        }
+        if KEYWORDS.contains(&name) {
+            found.push(Resolution::Keyword);
+        }
+        if BUILTINS.contains(&name) {
+            found.push(Resolution::Builtin);
+        }
+    }
+
+    if name.contains('/') {
+        if is_executable(Path::new(name)) {
+            found.push(Resolution::File(PathBuf::from(name), false));
+        }
+    } else if all {
+        for path in search_path_all(name) {
+            let hashed = state.hash.cached(name) == Some(path.as_path());
+            found.push(Resolution::File(path, hashed));
+        }
+    } else if let Some(path) = state.hash.cached(name) {
+        found.push(Resolution::File(path.to_path_buf(), true));
+    } else if let Some(path) = search_path(name) {
+        found.push(Resolution::File(path, false));
+    }
+
+    if !all {
+        found.truncate(1);
+    }
+    found
+}
+
+fn describe(name: &str, resolution: &Resolution) -> String {
+    match resolution {
+        Resolution::Alias(value) => format!("{} is aliased to `{}'", name, value),
+        Resolution::Keyword => format!("{} is a shell keyword", name),
+        Resolution::Builtin => format!("{} is a shell builtin", name),
+        Resolution::File(path, true) => format!("{} is hashed ({})", name, path.display()),
+        Resolution::File(path, false) => format!("{} is {}", name, path.display()),
+    }
+}
+
+pub fn type_builtin(args: Vec<String>, state: &ShellState) -> bool {
+    let mut all = false;
+    let mut kind_only = false;
+    let mut path_only = false;
+    let mut force_path = false;
+    let mut names = Vec::new();
+
+    for arg in args {
+        if names.is_empty() && arg.len() > 1 && arg.starts_with('-') {
+            for c in arg[1..].chars() {
+                match c {
+                    'a' => all = true,
+                    't' => kind_only = true,
+                    'p' => path_only = true,
+                    'P' => force_path = true,
+                    'f' => {}
+                    _ => {
+                        eprintln!("type: -{}: invalid option", c);
+                        eprintln!("type: usage: type [-afptP] name [name ...]");
+                        return false;
+                    }
+                }
+            }
+            continue;
+        }
+        names.push(arg);
+    }
+
+    let mut ok = true;
+    for name in names {
+        let resolutions = resolve(&name, state, all, force_path);
+        if resolutions.is_empty() {
+            if !kind_only && !path_only && !force_path {
+                eprintln!("type: {}: not found", name);
+            }
+            ok = false;
+            continue;
+        }
+
+        for resolution in &resolutions {
+            if kind_only {
+                let kind = match resolution {
+                    Resolution::Alias(_) => "alias",
+                    Resolution::Keyword => "keyword",
+                    Resolution::Builtin => "builtin",
+                    Resolution::File(..) => "file",
+                };
+                println!("{}", kind);
+            } else if path_only || force_path {
+                if let Resolution::File(path, _) = resolution {
+                    println!("{}", path.display());
+                }
+            } else {
+                println!("{}", describe(&name, resolution));
+            }
+        }
+    }
+    ok
+}
+
+pub fn which(args: Vec<String>, state: &ShellState) -> bool {
+    let all = args.iter().any(|arg| arg == "-a");
+    let mut ok = true;
+    for name in args.iter().filter(|arg| !arg.starts_with('-')) {
+        let paths: Vec<PathBuf> = resolve(name, state, all, true)
+            .into_iter()
+            .filter_map(|resolution| match resolution {
+                Resolution::File(path, _) => Some(path),
+                _ => None,
+            })
+            .collect();
+        if paths.is_empty() {
+            ok = false;
+        }
+        for path in paths {
+            println!("{}", path.display());
+        }
+    }
+    ok
+}
+
+pub fn command_lookup(names: &[String], state: &ShellState, verbose: bool) -> bool {
+    let mut ok = true;
+    for name in names {
+        match resolve(name, state, false, false).first() {
+            Some(resolution) if verbose => println!("{}", describe(name, resolution)),
+            Some(Resolution::Alias(value)) => println!("{}", format_alias(name, value)),
+            Some(Resolution::File(path, _)) => println!("{}", path.display()),
+            Some(_) => println!("{}", name),
+            None => {
+                if verbose {
+                    eprintln!("command: {}: not found", name);
+                }
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pwd_state::PwdState;
+    use crate::helpers::expand::set_variable;
+    use std::os::unix::fs::PermissionsExt;
+    use std::{env, fs, process};
+
+    fn executable(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn described(name: &str, state: &ShellState, all: bool, path_only: bool) -> Vec<String> {
+        resolve(name, state, all, path_only)
+            .iter()
+            .map(|resolution| describe(name, resolution))
+            .collect()
+    }
+
+    // Everything that depends on $PATH runs in this one test so the variable never
+    // changes under another one.
+    #[test]
+    fn resolution_order_is_alias_builtin_hashed_then_path() {
+        let root = env::temp_dir().join(format!("resolve-{}", process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        let echo = executable(&first, "echo");
+        let tool = executable(&first, "tool");
+        let later_tool = executable(&second, "tool");
+        fs::write(second.join("plain"), "").unwrap();
+
+        let saved_path = env::var("PATH").unwrap_or_default();
+        set_variable("PATH", &format!("{}:{}", first.display(), second.display()));
+        let mut state = ShellState::new(PwdState::new("/".to_string(), "/".to_string()));
+        state.aliases.set("echo".to_string(), "echo -n".to_string());
+
+        let type_all = described("echo", &state, true, false);
+        let command_v = described("echo", &state, false, false);
+        let which = described("echo", &state, false, true);
+        let keyword = described("[[", &state, true, false);
+        let missing = described("plain", &state, true, false);
+
+        let before_hash = described("tool", &state, true, false);
+        assert!(state.hash.remember("tool"));
+        set_variable("PATH", &format!("{}:{}", second.display(), first.display()));
+        let hashed = described("tool", &state, false, false);
+        let hashed_all = described("tool", &state, true, false);
+        let found = state.hash.find("tool");
+        fs::remove_file(&tool).unwrap();
+        let refound = state.hash.find("tool");
+
+        set_variable("PATH", &saved_path);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            type_all,
+            [
+                "echo is aliased to `echo -n'".to_string(),
+                "echo is a shell builtin".to_string(),
+                format!("echo is {}", echo.display()),
+            ]
+        );
+        assert_eq!(command_v, ["echo is aliased to `echo -n'"]);
+        assert_eq!(which, [format!("echo is {}", echo.display())]);
+        assert_eq!(keyword, ["[[ is a shell keyword"]);
+        assert!(missing.is_empty());
+        assert_eq!(
+            before_hash,
+            [
+                format!("tool is {}", tool.display()),
+                format!("tool is {}", later_tool.display()),
+            ]
+        );
+        assert_eq!(hashed, [format!("tool is hashed ({})", tool.display())]);
+        assert_eq!(
+            hashed_all,
+            [
+                format!("tool is {}", later_tool.display()),
+                format!("tool is hashed ({})", tool.display()),
+            ]
+        );
+        assert_eq!(found, Some(tool));
+        assert_eq!(refound, Some(later_tool));
+    }
+}
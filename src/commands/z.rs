@@ -0,0 +1,269 @@
+use crate::commands::cd::change_dir;
+use crate::commands::complete::BuiltinSpec;
+use crate::commands::pwd_state::{PwdState, logical_path};
+use chrono::Local;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+pub const Z_SPEC: BuiltinSpec = BuiltinSpec {
+    options: &["-l", "-x"],
+    actions: &[],
+};
+
+const MAX_TOTAL_RANK: f64 = 9000.0;
+
+#[derive(Debug, Clone)]
+struct Entry {
+    path: String,
+    rank: f64,
+    time: i64,
+}
+
+fn data_path() -> Option<PathBuf> {
+    env::var("_Z_DATA")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var("HOME")
+                .ok()
+                .map(|h| PathBuf::from(h).join(".0shell_z"))
+        })
+}
+
+// Lines are `path|rank|time`, as z.sh keeps them. Directories that no longer exist are
+// dropped on every load, so they disappear the next time the file is written.
+fn load() -> Vec<Entry> {
+    let Some(contents) = data_path().and_then(|p| fs::read_to_string(p).ok()) else {
+        return Vec::new();
+    };
+    parse(&contents)
+        .into_iter()
+        .filter(|entry| Path::new(&entry.path).is_dir())
+        .collect()
+}
+
+fn parse(contents: &str) -> Vec<Entry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.rsplitn(3, '|');
+            let time = fields.next()?.parse().ok()?;
+            let rank = fields.next()?.parse().ok()?;
+            let path = fields.next()?.to_string();
+            Some(Entry { path, rank, time })
+        })
+        .collect()
+}
+
+fn serialize(entries: &[Entry]) -> String {
+    entries
+        .iter()
+        .map(|e| format!("{}|{}|{}\n", e.path, e.rank, e.time))
+        .collect()
+}
+
+// Written beside the data file and renamed over it, so a shell exiting mid-write or
+// another shell saving at the same time never leaves a truncated database behind.
+fn save(entries: &[Entry]) {
+    let Some(path) = data_path() else {
+        return;
+    };
+    let mut temp = path.clone().into_os_string();
+    temp.push(format!(".{}", process::id()));
+    if fs::write(&temp, serialize(entries))
+        .and_then(|_| fs::rename(&temp, &path))
+        .is_err()
+    {
+        fs::remove_file(&temp).ok();
+    }
+}
+
+pub fn record(dir: &str) {
+    if dir.contains('\n') || env::var("HOME").is_ok_and(|home| home == dir) {
+        return;
+    }
+    let mut entries = load();
+    let now = Local::now().timestamp();
+    match entries.iter_mut().find(|e| e.path == dir) {
+        Some(entry) => {
+            entry.rank += 1.0;
+            entry.time = now;
+        }
+        None => entries.push(Entry {
+            path: dir.to_string(),
+            rank: 1.0,
+            time: now,
+        }),
+    }
+    // Once the ranks add up past the cap every entry fades a little, and the ones that
+    // fall below a single visit are forgotten.
+    if entries.iter().map(|e| e.rank).sum::<f64>() > MAX_TOTAL_RANK {
+        for entry in &mut entries {
+            entry.rank *= 0.99;
+        }
+        entries.retain(|e| e.rank >= 1.0);
+    }
+    save(&entries);
+}
+
+fn frecency(entry: &Entry, now: i64) -> f64 {
+    let age = now - entry.time;
+    if age < 3600 {
+        entry.rank * 4.0
+    } else if age < 86400 {
+        entry.rank * 2.0
+    } else if age < 604800 {
+        entry.rank / 2.0
+    } else {
+        entry.rank / 4.0
+    }
+}
+
+// Every fragment has to appear in the path, in the order given.
+fn matches(path: &str, fragments: &[String]) -> bool {
+    let mut rest = path;
+    for fragment in fragments {
+        match rest.find(fragment.as_str()) {
+            Some(i) => rest = &rest[i + fragment.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+// Matches ordered from lowest to highest score, trying case-insensitively when nothing
+// matches exactly.
+fn ranked(entries: &[Entry], fragments: &[String]) -> Vec<(f64, String)> {
+    let now = Local::now().timestamp();
+    let mut found: Vec<(f64, String)> = entries
+        .iter()
+        .filter(|e| matches(&e.path, fragments))
+        .map(|e| (frecency(e, now), e.path.clone()))
+        .collect();
+    if found.is_empty() {
+        let lowered: Vec<String> = fragments.iter().map(|f| f.to_lowercase()).collect();
+        found = entries
+            .iter()
+            .filter(|e| matches(&e.path.to_lowercase(), &lowered))
+            .map(|e| (frecency(e, now), e.path.clone()))
+            .collect();
+    }
+    found.sort_by(|a, b| a.0.total_cmp(&b.0));
+    found
+}
+
+pub fn z(args: Vec<String>, pwd_state: &mut PwdState) -> bool {
+    let mut list = false;
+    let mut forget = false;
+    let mut fragments = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-l" => list = true,
+            "-x" => forget = true,
+            _ if arg.len() > 1 && arg.starts_with('-') => {
+                eprintln!("z: {}: invalid option", arg);
+                eprintln!("z: usage: z [-l | -x] [pattern ...]");
+                return false;
+            }
+            _ => fragments.push(arg),
+        }
+    }
+
+    let mut entries = load();
+    if forget {
+        let current = pwd_state.get_current_dir();
+        let dir = match fragments.first() {
+            Some(dir) => logical_path(&current, Path::new(dir)).display().to_string(),
+            None => current,
+        };
+        let before = entries.len();
+        entries.retain(|e| e.path != dir);
+        save(&entries);
+        if entries.len() == before {
+            eprintln!("z: {}: not in the database", dir);
+            return false;
+        }
+        return true;
+    }
+
+    let found = ranked(&entries, &fragments);
+    if list || fragments.is_empty() {
+        for (score, path) in &found {
+            println!("{:<10} {}", format!("{:.0}", score), path);
+        }
+        return !found.is_empty();
+    }
+    match found.last() {
+        Some((_, path)) => change_dir("z", Path::new(path), path, false, pwd_state),
+        None => {
+            eprintln!("z: no match for {}", fragments.join(" "));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, rank: f64, time: i64) -> Entry {
+        Entry {
+            path: path.to_string(),
+            rank,
+            time,
+        }
+    }
+
+    fn fragments(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn fragments_match_in_order() {
+        assert!(matches("/home/me/src/shell", &fragments(&["src", "sh"])));
+        assert!(!matches(
+            "/home/me/src/shell",
+            &fragments(&["shell", "src"])
+        ));
+        assert!(!matches("/home/me/src", &fragments(&["src", "src"])));
+    }
+
+    #[test]
+    fn case_is_ignored_only_without_an_exact_match() {
+        let entries = [entry("/home/Docs", 1.0, 0), entry("/home/docs", 1.0, 0)];
+        let paths = |found: Vec<(f64, String)>| -> Vec<String> {
+            found.into_iter().map(|(_, path)| path).collect()
+        };
+        assert_eq!(
+            paths(ranked(&entries, &fragments(&["docs"]))),
+            ["/home/docs"]
+        );
+        assert_eq!(
+            paths(ranked(&entries, &fragments(&["DOCS"]))),
+            ["/home/Docs", "/home/docs"]
+        );
+    }
+
+    #[test]
+    fn recent_visits_weigh_more() {
+        let now = 1_000_000;
+        let rank = |age| frecency(&entry("/", 8.0, now - age), now);
+        assert_eq!(rank(60), 32.0);
+        assert_eq!(rank(7200), 16.0);
+        assert_eq!(rank(2 * 86400), 4.0);
+        assert_eq!(rank(30 * 86400), 2.0);
+    }
+
+    #[test]
+    fn entries_round_trip_through_the_data_file() {
+        let entries = [entry("/tmp/a|b", 2.5, 1700000000), entry("/srv", 1.0, 42)];
+        let parsed = parse(&serialize(&entries));
+        let fields: Vec<_> = parsed
+            .iter()
+            .map(|e| (e.path.as_str(), e.rank, e.time))
+            .collect();
+        assert_eq!(fields, [("/tmp/a|b", 2.5, 1700000000), ("/srv", 1.0, 42)]);
+        assert!(parse("/only|path\nbroken|x|1\n").is_empty());
+    }
+}
@@ -0,0 +1,488 @@
+use crate::commands::complete::{Action, CompletionSpec};
+use crate::commands::hash::is_executable;
+use crate::commands::shell_state::ShellState;
+use crate::helpers::expand::split_assignment;
+use crate::helpers::line_edit::display_width;
+use crate::helpers::parser::BUILTINS;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub replacement: String,
+    pub display: String,
+    pub word: String,
+}
+
+pub struct Completion {
+    pub start: usize,
+    pub candidates: Vec<Candidate>,
+    pub nospace: bool,
+}
+
+pub struct TabCycle {
+    start: usize,
+    end: usize,
+    candidates: Vec<Candidate>,
+    index: Option<usize>,
+    listed: bool,
+}
+
+pub struct TabResult {
+    pub line: String,
+    pub cursor: usize,
+    pub listing: Option<Vec<String>>,
+}
+
+pub fn complete_line(
+    line: &str,
+    cursor: usize,
+    state: &ShellState,
+    cycle: &mut Option<TabCycle>,
+) -> TabResult {
+    if let Some(cycle) = cycle.as_mut() {
+        let index = cycle.index.map_or(0, |i| (i + 1) % cycle.candidates.len());
+        let replacement = &cycle.candidates[index].replacement;
+        let new_line = format!(
+            "{}{}{}",
+            &line[..cycle.start],
+            replacement,
+            &line[cycle.end..]
+        );
+        cycle.index = Some(index);
+        cycle.end = cycle.start + replacement.len();
+
+        let listing = (!cycle.listed).then(|| displays(&cycle.candidates));
+        cycle.listed = true;
+        return TabResult {
+            line: new_line,
+            cursor: cycle.end,
+            listing,
+        };
+    }
+
+    let Completion {
+        start,
+        candidates,
+        nospace,
+    } = complete(&line[..cursor], state);
+    let (insertion, listing) = match candidates.len() {
+        0 => {
+            return TabResult {
+                line: line.to_string(),
+                cursor,
+                listing: None,
+            };
+        }
+        1 => {
+            let mut insertion = candidates[0].replacement.clone();
+            if !insertion.ends_with('/') && !nospace {
+                insertion.push(' ');
+            }
+            (insertion, None)
+        }
+        _ => {
+            let prefix = common_prefix(&candidates);
+            let stuck = prefix.len() <= cursor - start;
+            let listing = stuck.then(|| displays(&candidates));
+            *cycle = Some(TabCycle {
+                start,
+                end: start + prefix.len().max(cursor - start),
+                candidates,
+                index: None,
+                listed: stuck,
+            });
+            if stuck {
+                (line[start..cursor].to_string(), listing)
+            } else {
+                (prefix, listing)
+            }
+        }
+    };
+
+    TabResult {
+        line: format!("{}{}{}", &line[..start], insertion, &line[cursor..]),
+        cursor: start + insertion.len(),
+        listing,
+    }
+}
+
+pub fn complete(line: &str, state: &ShellState) -> Completion {
+    let (start, words) = current_word(line);
+    let raw = &line[start..];
+    let mut nospace = false;
+
+    let variable = raw.rfind('$').filter(|&i| {
+        let name = raw[i + 1..].strip_prefix('{').unwrap_or(&raw[i + 1..]);
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+
+    let mut candidates = if let Some(i) = variable {
+        variables(&raw[..i], &raw[i + 1..], true)
+    } else if let Some(name) = raw.strip_prefix('~')
+        && !name.contains('/')
+    {
+        users(name, true)
+    } else if words.is_empty() {
+        commands(raw, state)
+    } else {
+        match state.completions.get(&unquote(&words[0]), &words[1..]) {
+            Some(spec) => {
+                nospace = spec.nospace;
+                generate(&spec, raw, &words, line, state)
+            }
+            None => files(raw, FileFilter::All),
+        }
+    };
+
+    candidates.sort_by(|a, b| a.replacement.cmp(&b.replacement));
+    candidates.dedup();
+    Completion {
+        start,
+        candidates,
+        nospace,
+    }
+}
+
+pub fn generate(
+    spec: &CompletionSpec,
+    raw: &str,
+    words: &[String],
+    line: &str,
+    state: &ShellState,
+) -> Vec<Candidate> {
+    let word = unquote(raw);
+    let matching = |list: &[String]| -> Vec<Candidate> {
+        list.iter()
+            .filter(|w| w.starts_with(&word))
+            .map(|w| plain(w))
+            .collect()
+    };
+
+    if word.starts_with('-') && !spec.options.is_empty() {
+        return matching(&spec.options);
+    }
+
+    let mut candidates = matching(&spec.words);
+    for action in &spec.actions {
+        candidates.extend(match action {
+            Action::Alias => {
+                let names: Vec<String> = state.aliases.iter().map(|(n, _)| n.clone()).collect();
+                matching(&names)
+            }
+            Action::Builtin => {
+                let names: Vec<String> = BUILTINS.iter().map(|n| n.to_string()).collect();
+                matching(&names)
+            }
+            Action::Command => commands(raw, state),
+            Action::Directory => files(raw, FileFilter::Directories),
+            Action::File => files(raw, FileFilter::All),
+            Action::User => users(&word, false),
+            Action::Variable => variables("", &word, false),
+        });
+    }
+    if let Some(command) = &spec.command {
+        candidates.extend(
+            run_completer(command, words, &word, line)
+                .iter()
+                .map(|w| plain(w)),
+        );
+    }
+
+    if candidates.is_empty()
+        && spec.actions.is_empty()
+        && spec.words.is_empty()
+        && spec.command.is_none()
+    {
+        candidates = files(raw, FileFilter::All);
+    }
+    candidates
+}
+
+fn current_word(line: &str) -> (usize, Vec<String>) {
+    let mut start = 0;
+    let mut words: Vec<String> = Vec::new();
+    let mut single = false;
+    let mut double = false;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if !single => escaped = true,
+            '\'' if !double => single = !single,
+            '"' if !single => double = !double,
+            _ if single || double => {}
+            ';' | '|' | '&' => {
+                start = i + 1;
+                words.clear();
+            }
+            '>' => {
+                start = i + 1;
+                words.push(">".to_string());
+            }
+            c if c.is_whitespace() => {
+                if start < i {
+                    let word = &line[start..i];
+                    if !(words.is_empty() && split_assignment(word).is_some()) {
+                        words.push(word.to_string());
+                    }
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    (start, words)
+}
+
+fn unquote(raw: &str) -> String {
+    let mut out = String::new();
+    let mut single = false;
+    let mut double = false;
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if !single => {
+                if let Some(next) = chars.next() {
+                    out.push(next);
+                }
+            }
+            '\'' if !double => single = !single,
+            '"' if !single => double = !double,
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_whitespace() || "'\"\\$`|&;<>()*?[]!{}#".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn plain(word: &str) -> Candidate {
+    Candidate {
+        replacement: escape(word),
+        display: word.to_string(),
+        word: word.to_string(),
+    }
+}
+
+fn variables(before: &str, partial: &str, sigil: bool) -> Vec<Candidate> {
+    let (braced, prefix) = match partial.strip_prefix('{') {
+        Some(prefix) => (true, prefix),
+        None => (false, partial),
+    };
+    env::vars()
+        .map(|(name, _)| name)
+        .filter(|name| name.starts_with(prefix))
+        .map(|name| {
+            let word = match (sigil, braced) {
+                (false, _) => name.clone(),
+                (true, true) => format!("${{{}}}", name),
+                (true, false) => format!("${}", name),
+            };
+            Candidate {
+                replacement: format!("{}{}", before, word),
+                display: name,
+                word,
+            }
+        })
+        .collect()
+}
+
+fn users(prefix: &str, tilde: bool) -> Vec<Candidate> {
+    // SAFETY: the shell is single-threaded, so nothing else iterates the passwd database.
+    unsafe { users::all_users() }
+        .filter_map(|user| user.name().to_str().map(str::to_string))
+        .filter(|name| name.starts_with(prefix))
+        .map(|name| {
+            if tilde {
+                Candidate {
+                    replacement: format!("~{}/", name),
+                    display: format!("~{}", name),
+                    word: format!("~{}", name),
+                }
+            } else {
+                plain(&name)
+            }
+        })
+        .collect()
+}
+
+fn commands(raw: &str, state: &ShellState) -> Vec<Candidate> {
+    if raw.contains('/') {
+        return files(raw, FileFilter::Executables);
+    }
+
+    let prefix = unquote(raw);
+    let mut names: Vec<String> = BUILTINS
+        .iter()
+        .map(|name| name.to_string())
+        .chain(state.aliases.iter().map(|(name, _)| name.clone()))
+        .filter(|name| name.starts_with(&prefix))
+        .collect();
+
+    let path_var = env::var("PATH").unwrap_or_default();
+    for dir in path_var.split(':') {
+        let dir = if dir.is_empty() { "." } else { dir };
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(&prefix) && is_executable(&entry.path()) {
+                names.push(name);
+            }
+        }
+    }
+
+    names.iter().map(|name| plain(name)).collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FileFilter {
+    All,
+    Directories,
+    Executables,
+}
+
+fn files(raw: &str, filter: FileFilter) -> Vec<Candidate> {
+    let (dir_raw, partial) = match raw.rfind('/') {
+        Some(i) => (&raw[..i + 1], unquote(&raw[i + 1..])),
+        None => ("", unquote(raw)),
+    };
+    let dir_word = unquote(dir_raw);
+    let dir = expand_tilde(&dir_word);
+    let lookup = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir.as_path()
+    };
+
+    let Ok(entries) = fs::read_dir(lookup) else {
+        return Vec::new();
+    };
+
+    let mut candidates = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(&partial) || (name.starts_with('.') && !partial.starts_with('.')) {
+            continue;
+        }
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        let keep = match filter {
+            FileFilter::All => true,
+            FileFilter::Directories => is_dir,
+            FileFilter::Executables => is_dir || is_executable(&path),
+        };
+        if !keep {
+            continue;
+        }
+
+        let suffix = if is_dir { "/" } else { "" };
+        candidates.push(Candidate {
+            replacement: format!("{}{}{}", dir_raw, escape(&name), suffix),
+            display: format!("{}{}", name, suffix),
+            word: format!("{}{}", dir_word, name),
+        });
+    }
+    candidates
+}
+
+fn run_completer(command: &str, words: &[String], word: &str, line: &str) -> Vec<String> {
+    let mut parts = command.split_whitespace();
+    let Some(program) = parts.next() else {
+        return Vec::new();
+    };
+    let name = words.first().map(|w| unquote(w)).unwrap_or_default();
+    let previous = words.last().map(|w| unquote(w)).unwrap_or_default();
+
+    let output = process::Command::new(program)
+        .args(parts)
+        .args([name.as_str(), word, previous.as_str()])
+        .env("COMP_LINE", line)
+        .env("COMP_POINT", line.len().to_string())
+        .stdin(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .output();
+    match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix('~') {
+        let (user, tail) = rest.split_once('/').unwrap_or((rest, ""));
+        let home = if user.is_empty() {
+            env::var("HOME").ok().map(PathBuf::from)
+        } else {
+            users::get_user_by_name(user).map(|u| {
+                use users::os::unix::UserExt;
+                u.home_dir().to_path_buf()
+            })
+        };
+        if let Some(home) = home {
+            return home.join(tail);
+        }
+    }
+    PathBuf::from(path)
+}
+
+fn common_prefix(candidates: &[Candidate]) -> String {
+    let first = &candidates[0].replacement;
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.replacement.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+fn displays(candidates: &[Candidate]) -> Vec<String> {
+    candidates.iter().map(|c| c.display.clone()).collect()
+}
+
+pub fn format_columns(items: &[String], width: usize) -> String {
+    let column_width = items.iter().map(|i| display_width(i)).max().unwrap_or(0) + 2;
+    let columns = (width / column_width).max(1);
+    let rows = items.len().div_ceil(columns);
+
+    let mut out = String::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let Some(item) = items.get(column * rows + row) else {
+                continue;
+            };
+            out.push_str(item);
+            if column + 1 < columns && (column + 1) * rows + row < items.len() {
+                let pad = column_width - display_width(item);
+                out.push_str(&" ".repeat(pad));
+            }
+        }
+        out.push_str("\r\n");
+    }
+    out
+}
@@ -1,70 +1,511 @@
 use crate::commands::{
-    cat::cat, cd::command_cd, cp::*, echo::*, exit::exit, ls::ls, mv::mv, pwd_state::PwdState,
-    rm::rm,
+    alias::*, cat::cat, cd::command_cd, complete::*, cp::*, dirs::*, echo::*, exit::exit,
+    hash::hash, history::history, ls::ls, mkdir::mkdir, mv::mv, printf::printf, pwd::pwd,
+    read::read, rm::rm, set::set, shell_options::ShellOption, shell_state::ShellState, test::*,
+    type_builtin::*, z::z,
+};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    os::fd::{AsRawFd, FromRawFd},
+    os::unix::process::{CommandExt, ExitStatusExt},
+    path::Path,
+    process,
 };
 
+use super::expand::*;
 use super::parser::*;
 
-pub fn execute(cmd: CommandEnum, pwd_state: &mut PwdState) -> bool {
+pub fn execute(cmd: CommandEnum, state: &mut ShellState) -> i32 {
     let succes = match cmd {
         CommandEnum::Mv(c) => mv(c),
         CommandEnum::Ls(c) => ls(c),
         CommandEnum::Rm(c) => {
             if c.is_empty() {
                 println!("rm: missing operand");
-                return false;
+                false
             } else {
-                return rm(c);
+                rm(c)
             }
         }
         CommandEnum::Cat(c) => cat(c),
-        CommandEnum::Cp(c) => {
-            return cp(c);
+        CommandEnum::Cp(c) => cp(c),
+        CommandEnum::Pwd(args) => pwd(args, &state.pwd),
+
+        CommandEnum::Mkdir(dir, error_dir) => mkdir(dir, error_dir),
+
+        CommandEnum::Cd(path, error_path) => command_cd(path, error_path, &mut state.pwd),
+
+        CommandEnum::Echo(args) => {
+            echo(args);
+            true
         }
-        CommandEnum::Pwd => {
-            let pwd = pwd_state.get_current_dir().replace("\n", "\\n");
-            println!("{}", pwd);
-            return true;
+
+        CommandEnum::Set(args) => set(args, &mut state.options),
+
+        CommandEnum::Test(args, bracket) => return test(args, bracket),
+
+        CommandEnum::Read(args) => read(args),
+
+        CommandEnum::Printf(args) => printf(args),
+
+        CommandEnum::Type(args) => type_builtin(args, state),
+
+        CommandEnum::Which(args) => which(args, state),
+
+        CommandEnum::Command(args) => return command(args, state),
+
+        CommandEnum::Hash(args) => hash(args, &mut state.hash),
+
+        CommandEnum::Alias(args) => alias(args, &mut state.aliases),
+
+        CommandEnum::Unalias(args) => unalias(args, &mut state.aliases),
+
+        CommandEnum::History(args) => history(args, &mut state.history),
+
+        CommandEnum::Complete(args) => complete(args, state),
+
+        CommandEnum::Compgen(args) => compgen(args, state),
+
+        CommandEnum::Pushd(args) => pushd(args, &mut state.pwd),
+
+        CommandEnum::Popd(args) => popd(args, &mut state.pwd),
+
+        CommandEnum::Dirs(args) => dirs(args, &mut state.pwd),
+
+        CommandEnum::Z(args) => z(args, &mut state.pwd),
+
+        CommandEnum::Clear => {
+            super::parser::clear();
+            true
+        }
+
+        CommandEnum::Exit => {
+            exit();
+            true
+        }
+
+        CommandEnum::Unknown(args)
+            if state.options.is_set(ShellOption::Autocd)
+                && args.len() == 1
+                && Path::new(&args[0]).is_dir() =>
+        {
+            println!("cd -- {}", args[0]);
+            let args = vec!["--".to_string(), args[0].clone()];
+            command_cd(args.clone(), args, &mut state.pwd)
+        }
+
+        CommandEnum::Unknown(args) => return run_external(args, state),
+    };
+    if succes { 0 } else { 1 }
+}
+
+fn command(args: Vec<String>, state: &mut ShellState) -> i32 {
+    let mut lookup = None;
+    let mut first_word = 0;
+    for arg in &args {
+        match arg.as_str() {
+            "-v" => lookup = Some(false),
+            "-V" => lookup = Some(true),
+            "-p" => {}
+            "--" => {
+                first_word += 1;
+                break;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                eprintln!("command: {}: invalid option", arg);
+                eprintln!("command: usage: command [-pVv] command [arg ...]");
+                return 2;
+            }
+            _ => break,
+        }
+        first_word += 1;
+    }
+
+    let words = args[first_word..].to_vec();
+    if let Some(verbose) = lookup {
+        return if command_lookup(&words, state, verbose) {
+            0
+        } else {
+            1
+        };
+    }
+    if words.is_empty() {
+        return 0;
+    }
+    execute(build_command(words), state)
+}
+
+fn run_external(args: Vec<String>, state: &mut ShellState) -> i32 {
+    let name = &args[0];
+    let Some(path) = state.hash.find(name) else {
+        eprintln!("command not found: {}", name.replace("\n", "\\n"));
+        return 127;
+    };
+
+    let mut child = process::Command::new(&path);
+    child.arg0(name).args(&args[1..]);
+    // SAFETY: only async-signal-safe calls run between fork and exec.
+    unsafe {
+        child.pre_exec(|| {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+            libc::signal(libc::SIGQUIT, libc::SIG_DFL);
+            Ok(())
+        });
+    }
+
+    io::stdout().flush().ok();
+    match child.status() {
+        Ok(status) => status
+            .code()
+            .unwrap_or_else(|| 128 + status.signal().unwrap_or(0)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("0-shell: {}: No such file or directory", name);
+            127
+        }
+        Err(e) => {
+            eprintln!("0-shell: {}: {}", name, e);
+            126
+        }
+    }
+}
+
+pub fn run_pipeline(pipeline: &Pipeline, state: &mut ShellState) -> Result<i32, String> {
+    if pipeline.commands.len() == 1 {
+        return run_simple(&pipeline.commands[0], state);
+    }
+
+    io::stdout().flush().ok();
+    let mut children = Vec::new();
+    let mut input_fd: Option<i32> = None;
+
+    for (i, command) in pipeline.commands.iter().enumerate() {
+        let is_last = i + 1 == pipeline.commands.len();
+        let mut fds = [-1; 2];
+        if !is_last && unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            eprintln!("0-shell: pipe: {}", io::Error::last_os_error());
+            break;
+        }
+
+        match unsafe { libc::fork() } {
+            -1 => {
+                eprintln!("0-shell: fork: {}", io::Error::last_os_error());
+                break;
+            }
+            0 => {
+                unsafe {
+                    libc::signal(libc::SIGINT, libc::SIG_DFL);
+                    if let Some(fd) = input_fd {
+                        libc::dup2(fd, 0);
+                        libc::close(fd);
+                    }
+                    if !is_last {
+                        libc::dup2(fds[1], 1);
+                        libc::close(fds[0]);
+                        libc::close(fds[1]);
+                    }
+                }
+                let code = match run_simple(command, state) {
+                    Ok(code) => code,
+                    Err(msg) => {
+                        eprintln!("0-shell: {}", msg);
+                        1
+                    }
+                };
+                io::stdout().flush().ok();
+                unsafe { libc::_exit(code) };
+            }
+            pid => {
+                children.push(pid);
+                if let Some(fd) = input_fd.take() {
+                    unsafe { libc::close(fd) };
+                }
+                if !is_last {
+                    unsafe { libc::close(fds[1]) };
+                    input_fd = Some(fds[0]);
+                }
+            }
         }
+    }
+
+    if let Some(fd) = input_fd {
+        unsafe { libc::close(fd) };
+    }
+
+    let statuses: Vec<i32> = children.into_iter().map(wait_for).collect();
+    let status = if state.options.is_set(ShellOption::Pipefail) {
+        statuses
+            .iter()
+            .rev()
+            .find(|s| **s != 0)
+            .copied()
+            .unwrap_or(0)
+    } else {
+        statuses.last().copied().unwrap_or(1)
+    };
+    Ok(status)
+}
+
+// Runs `command` in a forked copy of the shell and returns what it printed, minus
+// trailing newlines, the way command substitution does.
+pub fn command_output(command: &str, state: &ShellState) -> String {
+    let ParseResult::Ok(list) = parse_input(command, &state.aliases) else {
+        return String::new();
+    };
 
-        CommandEnum::Mkdir(dir, error_dir) => {
-            if dir.is_empty() {
-                println!("mkdir: missing operand");
-                return false;
+    io::stdout().flush().ok();
+    let mut fds = [-1; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return String::new();
+    }
+
+    match unsafe { libc::fork() } {
+        -1 => {
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+            String::new()
+        }
+        0 => {
+            unsafe {
+                libc::signal(libc::SIGINT, libc::SIG_DFL);
+                libc::dup2(fds[1], 1);
+                libc::close(fds[0]);
+                libc::close(fds[1]);
             }
-            let mut count = 0;
-            for d in dir {
-                count += 1;
-                if let Err(e) = std::fs::create_dir(&d) {
-                    eprintln!(
-                        "mkdir: cannot create directory '{}': {}",
-                        error_dir[count - 1],
-                        e
-                    );
-                    return false;
+            let mut state = state.clone();
+            execute_all(list, &mut state);
+            io::stdout().flush().ok();
+            unsafe { libc::_exit(state.last_status) };
+        }
+        pid => {
+            unsafe { libc::close(fds[1]) };
+            // SAFETY: the read end was just created by pipe() and nothing else owns it.
+            let mut reader = unsafe { File::from_raw_fd(fds[0]) };
+            let mut output = Vec::new();
+            reader.read_to_end(&mut output).ok();
+            wait_for(pid);
+            let output = String::from_utf8_lossy(&output);
+            output.trim_end_matches('\n').to_string()
+        }
+    }
+}
+
+fn wait_for(pid: libc::pid_t) -> i32 {
+    let mut status = 0;
+    if unsafe { libc::waitpid(pid, &mut status, 0) } == -1 {
+        return 1;
+    }
+    if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else if libc::WIFSIGNALED(status) {
+        128 + libc::WTERMSIG(status)
+    } else {
+        1
+    }
+}
+
+fn run_simple(command: &SimpleCommand, state: &mut ShellState) -> Result<i32, String> {
+    // `[[` expands its own operands lazily, so tracing it must not fail on an unset word.
+    let is_conditional = command.words.first().is_some_and(|word| word == "[[");
+
+    let mut assignments = Vec::new();
+    let mut args = Vec::new();
+    if !is_conditional {
+        for raw in &command.words {
+            match split_assignment(raw) {
+                Some((name, value)) => {
+                    assignments.push((name.to_string(), expand_word(value, state)?))
                 }
+                None => break,
             }
-            return true;
         }
+        args = expand_words(&command.words[assignments.len()..], state)?;
+    }
 
-        CommandEnum::Cd(path, error_path) => {
-            return command_cd(path, error_path, pwd_state);
+    let mut redirects = Vec::new();
+    for redirect in &command.redirects {
+        redirects.push((redirect.kind, expand_word(&redirect.target, state)?));
+    }
+
+    if state.options.is_set(ShellOption::Xtrace) {
+        if is_conditional {
+            let words: Vec<String> = command
+                .words
+                .iter()
+                .map(|raw| expand_word(raw, state).unwrap_or_else(|_| raw.clone()))
+                .collect();
+            trace(&words);
+        } else {
+            let words: Vec<String> = assignments
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, shell_quote(value)))
+                .chain(args.iter().map(|arg| shell_quote(arg)))
+                .collect();
+            trace(&words);
         }
+    }
 
-        CommandEnum::Echo(args) => {
-            echo(args);
-            return true;
+    let saved_stdout =
+        match apply_redirects(&redirects, state.options.is_set(ShellOption::Noclobber)) {
+            Ok(saved) => saved,
+            Err(msg) => {
+                eprintln!("0-shell: {}", msg);
+                return Ok(1);
+            }
+        };
+
+    if is_conditional {
+        let status = conditional(&command.words[1..], state);
+        restore_stdout(saved_stdout);
+        return Ok(status);
+    }
+
+    if assignments.iter().any(|(name, _)| name == "PATH") {
+        state.hash.clear();
+    }
+
+    if args.is_empty() {
+        for (name, value) in &assignments {
+            set_variable(name, value);
         }
+        restore_stdout(saved_stdout);
+        return Ok(0);
+    }
 
-        CommandEnum::Exit => {
-            exit();
-            return true;
+    let previous: Vec<(String, Option<String>)> = assignments
+        .iter()
+        .map(|(name, _)| (name.clone(), env::var(name).ok()))
+        .collect();
+    for (name, value) in &assignments {
+        set_variable(name, value);
+    }
+
+    let status = execute(build_command(args), state);
+
+    for (name, value) in previous {
+        match value {
+            Some(value) => set_variable(&name, &value),
+            None => unset_variable(&name),
         }
+    }
+    restore_stdout(saved_stdout);
+
+    Ok(status)
+}
 
-        CommandEnum::Unknown(cmd) => {
-            eprintln!("command not found: {}", cmd.replace("\n", "\\n"));
-            return false;
+fn trace(words: &[String]) {
+    let ps4 = env::var("PS4").unwrap_or_else(|_| "+ ".to_string());
+    eprintln!("{}{}", ps4, words.join(" "));
+}
+
+fn open_redirect(kind: RedirectKind, target: &str, noclobber: bool) -> Result<File, String> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if kind == RedirectKind::Truncate && noclobber {
+        match options.clone().create_new(true).open(target) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if fs::metadata(target).is_ok_and(|m| m.is_file()) {
+                    return Err(format!("{}: cannot overwrite existing file", target));
+                }
+            }
+            result => return result.map_err(|e| format!("{}: {}", target, e)),
         }
-    };
-    succes
+    } else if kind == RedirectKind::Append {
+        options.create(true).append(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    options
+        .open(target)
+        .map_err(|e| format!("{}: {}", target, e))
+}
+
+fn apply_redirects(
+    redirects: &[(RedirectKind, String)],
+    noclobber: bool,
+) -> Result<Option<i32>, String> {
+    let mut saved = None;
+    for (kind, target) in redirects {
+        let file = match open_redirect(*kind, target, noclobber) {
+            Ok(file) => file,
+            Err(msg) => {
+                restore_stdout(saved);
+                return Err(msg);
+            }
+        };
+        io::stdout().flush().ok();
+        if saved.is_none() {
+            saved = Some(unsafe { libc::dup(1) });
+        }
+        unsafe { libc::dup2(file.as_raw_fd(), 1) };
+    }
+    Ok(saved)
+}
+
+fn restore_stdout(saved: Option<i32>) {
+    if let Some(fd) = saved {
+        io::stdout().flush().ok();
+        unsafe {
+            libc::dup2(fd, 1);
+            libc::close(fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pwd_state::PwdState;
+
+    fn shell(options: &[ShellOption]) -> ShellState {
+        let mut state = ShellState::new(PwdState::new("/".to_string(), "/".to_string()));
+        for &option in options {
+            state.options.set(option, true);
+        }
+        state
+    }
+
+    #[test]
+    fn errexit_spares_the_left_side_of_and_or_lists() {
+        let state = shell(&[ShellOption::Errexit]);
+        let run = |line: &str| command_output(line, &state);
+        assert_eq!(run("test a = b && echo no; echo after"), "after");
+        assert_eq!(
+            run("test a = b || echo rescued; echo after"),
+            "rescued\nafter"
+        );
+        assert_eq!(run("echo x && test a = b; echo after"), "x");
+        assert_eq!(run("echo before; test a = b; echo after"), "before");
+    }
+
+    #[test]
+    fn pipefail_reports_the_last_failing_command() {
+        let line = "test a = b | echo piped; echo $?";
+        assert_eq!(command_output(line, &shell(&[])), "piped\n0");
+        let state = shell(&[ShellOption::Pipefail]);
+        assert_eq!(command_output(line, &state), "piped\n1");
+        assert_eq!(command_output("echo x | test a = a; echo $?", &state), "0");
+    }
+
+    #[test]
+    fn noclobber_refuses_to_truncate_existing_files() {
+        let path = env::temp_dir().join(format!("noclobber-{}", process::id()));
+        let target = path.display().to_string();
+        let state = shell(&[ShellOption::Noclobber]);
+        command_output(&format!("echo one > {}", target), &state);
+        command_output(&format!("echo two > {}", target), &state);
+        let kept = fs::read_to_string(&path).unwrap();
+        command_output(&format!("echo three >> {}", target), &state);
+        let appended = fs::read_to_string(&path).unwrap();
+        command_output(&format!("echo four >| {}", target), &state);
+        let forced = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(kept, "one\n");
+        assert_eq!(appended, "one\nthree\n");
+        assert_eq!(forced, "four\n");
+        assert!(open_redirect(RedirectKind::Truncate, "/dev/null", true).is_ok());
+    }
 }
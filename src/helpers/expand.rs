@@ -0,0 +1,232 @@
+use crate::commands::shell_options::ShellOption;
+use crate::commands::shell_state::ShellState;
+use std::{env, iter::Peekable, str::Chars};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Normal,
+    Single,
+    Double,
+}
+
+pub fn expand_words(raw_words: &[String], state: &ShellState) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    for raw in raw_words {
+        let word = expand_word(raw, state)?;
+        let quoted = raw.contains(['\'', '"', '\\']);
+        if !word.is_empty() || quoted {
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+pub fn expand_word(raw: &str, state: &ShellState) -> Result<String, String> {
+    expand(raw, state, "")
+}
+
+pub fn expand_pattern(raw: &str, state: &ShellState) -> Result<String, String> {
+    expand(raw, state, "*?[]\\")
+}
+
+pub fn expand_regex(raw: &str, state: &ShellState) -> Result<String, String> {
+    expand(raw, state, "\\.[](){}*+?|^$")
+}
+
+fn push_literal(word: &mut String, text: &str, specials: &str) {
+    for c in text.chars() {
+        if specials.contains(c) {
+            word.push('\\');
+        }
+        word.push(c);
+    }
+}
+
+// A leading unquoted `~` names a home directory, `~+` and `~-` the current and previous
+// directories, and `~N`, `~+N` or `~-N` an entry of the directory stack.
+fn tilde_prefix<'a>(raw: &'a str, state: &ShellState) -> Option<(String, &'a str)> {
+    let rest = raw.strip_prefix('~')?;
+    let end = rest.find('/').unwrap_or(rest.len());
+    let name = &rest[..end];
+    if name.contains(['\'', '"', '\\', '$']) {
+        return None;
+    }
+    let dir = match name {
+        "" => env::var("HOME").ok()?,
+        "+" => state.pwd.get_current_dir(),
+        "-" => state.pwd.get_old_dir(),
+        _ => match state.pwd.stack_index(name) {
+            Some(index) => state.pwd.get_dirs().swap_remove(index),
+            None => {
+                use users::os::unix::UserExt;
+                let user = users::get_user_by_name(name)?;
+                user.home_dir().to_string_lossy().into_owned()
+            }
+        },
+    };
+    Some((dir, &rest[end..]))
+}
+
+fn expand(raw: &str, state: &ShellState, specials: &str) -> Result<String, String> {
+    let mut word = String::new();
+    let mut mode = Mode::Normal;
+    let mut escaped = false;
+    let mut rest = raw;
+    if let Some((dir, tail)) = tilde_prefix(raw, state) {
+        push_literal(&mut word, &dir, specials);
+        rest = tail;
+    }
+    let mut chars = rest.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if escaped {
+            if mode == Mode::Double && !matches!(c, '"' | '\\' | '$') {
+                push_literal(&mut word, "\\", specials);
+            }
+            push_literal(&mut word, c.encode_utf8(&mut [0; 4]), specials);
+            escaped = false;
+            continue;
+        }
+
+        match mode {
+            Mode::Normal => match c {
+                '\\' => escaped = true,
+                '\'' => mode = Mode::Single,
+                '"' => mode = Mode::Double,
+                '$' => match expand_parameter(&mut chars, state)? {
+                    Some(value) => word.push_str(&value),
+                    None => word.push('$'),
+                },
+                _ => word.push(c),
+            },
+            Mode::Single => {
+                if c == '\'' {
+                    mode = Mode::Normal;
+                } else {
+                    push_literal(&mut word, c.encode_utf8(&mut [0; 4]), specials);
+                }
+            }
+            Mode::Double => match c {
+                '\\' => escaped = true,
+                '"' => mode = Mode::Normal,
+                '$' => {
+                    let value = expand_parameter(&mut chars, state)?;
+                    push_literal(&mut word, value.as_deref().unwrap_or("$"), specials);
+                }
+                _ => push_literal(&mut word, c.encode_utf8(&mut [0; 4]), specials),
+            },
+        }
+    }
+    Ok(word)
+}
+
+pub fn expand_parameter(
+    chars: &mut Peekable<Chars>,
+    state: &ShellState,
+) -> Result<Option<String>, String> {
+    let value = match chars.peek().copied() {
+        Some('?') => {
+            chars.next();
+            state.last_status.to_string()
+        }
+        Some('$') => {
+            chars.next();
+            std::process::id().to_string()
+        }
+        Some('{') => {
+            chars.next();
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) => name.push(c),
+                    None => return Err(format!("${{{}: bad substitution", name)),
+                }
+            }
+            if !is_name(&name) {
+                return Err(format!("${{{}}}: bad substitution", name));
+            }
+            lookup_variable(&name, state)?
+        }
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+            lookup_variable(&name, state)?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+fn lookup_variable(name: &str, state: &ShellState) -> Result<String, String> {
+    match env::var(name) {
+        Ok(value) => Ok(value),
+        Err(_) if state.options.is_set(ShellOption::Nounset) => {
+            Err(format!("{}: unbound variable", name))
+        }
+        Err(_) => Ok(String::new()),
+    }
+}
+
+pub fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn split_assignment(raw: &str) -> Option<(&str, &str)> {
+    let (name, value) = raw.split_once('=')?;
+    if is_name(name) {
+        Some((name, value))
+    } else {
+        None
+    }
+}
+
+pub fn set_variable(name: &str, value: &str) {
+    // SAFETY: the shell is single-threaded, so nothing reads the environment concurrently.
+    unsafe { env::set_var(name, value) };
+}
+
+pub fn unset_variable(name: &str) {
+    // SAFETY: see `set_variable`.
+    unsafe { env::remove_var(name) };
+}
+
+pub fn shell_quote(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pwd_state::PwdState;
+
+    #[test]
+    fn tilde_numbers_name_stack_entries() {
+        let mut state = ShellState::new(PwdState::new("/a".to_string(), "/old".to_string()));
+        state
+            .pwd
+            .stack_mut()
+            .extend(["/b".to_string(), "/c".to_string()]);
+        let expand = |raw: &str| expand_word(raw, &state).unwrap();
+        assert_eq!(expand("~0"), "/a");
+        assert_eq!(expand("~+1/x"), "/b/x");
+        assert_eq!(expand("~-0"), "/c");
+        assert_eq!(expand("~+"), "/a");
+        assert_eq!(expand("~-"), "/old");
+        assert_eq!(expand("~3"), "~3");
+        assert_eq!(expand("~-18446744073709551615"), "~-18446744073709551615");
+        assert_eq!(expand("'~1'"), "~1");
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone)]
+struct Repository {
+    work_tree: PathBuf,
+    git_dir: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    path: String,
+    mtime: u32,
+    mtime_nsec: u32,
+    size: u32,
+    conflicted: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Index {
+    modified: SystemTime,
+    len: u64,
+    entries: Option<Vec<IndexEntry>>,
+}
+
+// Repository locations are cached per directory and parsed indexes per repository,
+// so a prompt only re-reads what git itself has rewritten since the last one.
+#[derive(Debug, Clone, Default)]
+pub struct GitCache {
+    repositories: HashMap<PathBuf, Repository>,
+    indexes: HashMap<PathBuf, Index>,
+}
+
+impl GitCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Formats the branch the way git's own __git_ps1 does: " (main *|MERGING)".
+    pub fn segment(&mut self, dir: &Path) -> String {
+        let Some(repository) = self.repository(dir) else {
+            return String::new();
+        };
+        let git_dir = &repository.git_dir;
+        let (mut branch, operation) = operation(git_dir);
+        if branch.is_none() {
+            branch = head(git_dir);
+        }
+        let Some(branch) = branch else {
+            return String::new();
+        };
+
+        let dirty = self
+            .index(git_dir)
+            .is_some_and(|entries| is_dirty(&repository.work_tree, entries));
+        format!(
+            " ({}{}{})",
+            branch,
+            if dirty { " *" } else { "" },
+            operation
+        )
+    }
+
+    fn repository(&mut self, dir: &Path) -> Option<Repository> {
+        if let Some(repository) = self.repositories.get(dir)
+            && repository.git_dir.is_dir()
+        {
+            return Some(repository.clone());
+        }
+        let repository = find_repository(dir)?;
+        self.repositories
+            .insert(dir.to_path_buf(), repository.clone());
+        Some(repository)
+    }
+
+    fn index(&mut self, git_dir: &Path) -> Option<&[IndexEntry]> {
+        let path = git_dir.join("index");
+        let metadata = fs::metadata(&path).ok()?;
+        let modified = metadata.modified().ok()?;
+        let fresh = self
+            .indexes
+            .get(git_dir)
+            .is_some_and(|index| index.modified == modified && index.len == metadata.len());
+        if !fresh {
+            let hash_len = if fs::read_to_string(git_dir.join("config"))
+                .is_ok_and(|config| config.contains("objectformat = sha256"))
+            {
+                32
+            } else {
+                20
+            };
+            let entries = fs::read(&path)
+                .ok()
+                .and_then(|data| parse_index(&data, hash_len));
+            self.indexes.insert(
+                git_dir.to_path_buf(),
+                Index {
+                    modified,
+                    len: metadata.len(),
+                    entries,
+                },
+            );
+        }
+        self.indexes.get(git_dir)?.entries.as_deref()
+    }
+}
+
+fn find_repository(dir: &Path) -> Option<Repository> {
+    for candidate in dir.ancestors() {
+        let dot_git = candidate.join(".git");
+        if dot_git.is_dir() {
+            return Some(Repository {
+                work_tree: candidate.to_path_buf(),
+                git_dir: dot_git,
+            });
+        }
+        // Worktrees and submodules leave a file pointing at the real git directory.
+        if let Ok(contents) = fs::read_to_string(&dot_git)
+            && let Some(target) = contents.trim().strip_prefix("gitdir: ")
+        {
+            return Some(Repository {
+                work_tree: candidate.to_path_buf(),
+                git_dir: candidate.join(target),
+            });
+        }
+    }
+    None
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|contents| contents.trim().to_string())
+}
+
+fn short_ref(name: &str) -> String {
+    name.strip_prefix("refs/heads/").unwrap_or(name).to_string()
+}
+
+fn head(git_dir: &Path) -> Option<String> {
+    let head = read_trimmed(&git_dir.join("HEAD"))?;
+    match head.strip_prefix("ref: ") {
+        Some(name) => Some(short_ref(name)),
+        None => Some(format!("{}...", head.get(..7)?)),
+    }
+}
+
+// Reports an in-progress operation, along with the branch a rebase started from.
+fn operation(git_dir: &Path) -> (Option<String>, String) {
+    let rebase_merge = git_dir.join("rebase-merge");
+    let rebase_apply = git_dir.join("rebase-apply");
+
+    let (branch, label, step, total) = if rebase_merge.is_dir() {
+        let label = if rebase_merge.join("interactive").exists() {
+            "REBASE-i"
+        } else {
+            "REBASE-m"
+        };
+        (
+            read_trimmed(&rebase_merge.join("head-name")),
+            label,
+            read_trimmed(&rebase_merge.join("msgnum")),
+            read_trimmed(&rebase_merge.join("end")),
+        )
+    } else if rebase_apply.is_dir() {
+        let label = if rebase_apply.join("rebasing").exists() {
+            "REBASE"
+        } else if rebase_apply.join("applying").exists() {
+            "AM"
+        } else {
+            "AM/REBASE"
+        };
+        (
+            read_trimmed(&rebase_apply.join("head-name")),
+            label,
+            read_trimmed(&rebase_apply.join("next")),
+            read_trimmed(&rebase_apply.join("last")),
+        )
+    } else {
+        let label = [
+            ("MERGE_HEAD", "MERGING"),
+            ("CHERRY_PICK_HEAD", "CHERRY-PICKING"),
+            ("REVERT_HEAD", "REVERTING"),
+            ("BISECT_LOG", "BISECTING"),
+        ]
+        .into_iter()
+        .find(|(file, _)| git_dir.join(file).exists())
+        .map_or(String::new(), |(_, label)| format!("|{}", label));
+        return (None, label);
+    };
+
+    let progress = match (step, total) {
+        (Some(step), Some(total)) => format!(" {}/{}", step, total),
+        _ => String::new(),
+    };
+    (
+        branch.map(|name| short_ref(&name)),
+        format!("|{}{}", label, progress),
+    )
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+// Reads the stat data git recorded for each tracked file. Only index versions 2 and 3
+// are understood; version 4 compresses paths and is skipped.
+fn parse_index(data: &[u8], hash_len: usize) -> Option<Vec<IndexEntry>> {
+    if data.get(..4)? != b"DIRC" {
+        return None;
+    }
+    let version = read_u32(data, 4)?;
+    if !(2..=3).contains(&version) {
+        return None;
+    }
+    let count = read_u32(data, 8)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    let mut offset = 12;
+    for _ in 0..count {
+        let flags_at = offset + 40 + hash_len;
+        let mode = read_u32(data, offset + 24)?;
+        let flags = u16::from_be_bytes(data.get(flags_at..flags_at + 2)?.try_into().ok()?);
+        let extended = flags & 0x4000 != 0;
+        let extended_flags = if extended {
+            u16::from_be_bytes(data.get(flags_at + 2..flags_at + 4)?.try_into().ok()?)
+        } else {
+            0
+        };
+        let name_start = flags_at + 2 + if extended { 2 } else { 0 };
+        let name_len = data.get(name_start..)?.iter().position(|&b| b == 0)?;
+
+        let assume_valid = flags & 0x8000 != 0;
+        let skip_worktree = extended_flags & 0x4000 != 0;
+        let submodule = mode & 0o170000 == 0o160000;
+        if !assume_valid && !skip_worktree && !submodule {
+            entries.push(IndexEntry {
+                path: String::from_utf8_lossy(&data[name_start..name_start + name_len])
+                    .into_owned(),
+                mtime: read_u32(data, offset + 8)?,
+                mtime_nsec: read_u32(data, offset + 12)?,
+                size: read_u32(data, offset + 36)?,
+                conflicted: (flags >> 12) & 0x3 != 0,
+            });
+        }
+        offset += (name_start - offset + name_len + 8) & !7;
+    }
+    Some(entries)
+}
+
+// A tracked file is dirty when its size or modification time no longer matches what
+// the index recorded, which is the same quick check `git status` starts from. Files
+// written in place leave their directory untouched, so every prompt stats them all.
+fn is_dirty(work_tree: &Path, entries: &[IndexEntry]) -> bool {
+    entries.iter().any(|entry| {
+        let Ok(metadata) = fs::symlink_metadata(work_tree.join(&entry.path)) else {
+            return true;
+        };
+        entry.conflicted
+            || metadata.size() as u32 != entry.size
+            || metadata.mtime() as u32 != entry.mtime
+            || (entry.mtime_nsec != 0 && metadata.mtime_nsec() as u32 != entry.mtime_nsec)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, flags: u16, extended_flags: Option<u16>, mode: u32) -> Vec<u8> {
+        let mut data = vec![0; 40 + 20];
+        data[8..12].copy_from_slice(&7u32.to_be_bytes());
+        data[12..16].copy_from_slice(&9u32.to_be_bytes());
+        data[24..28].copy_from_slice(&mode.to_be_bytes());
+        data[36..40].copy_from_slice(&5u32.to_be_bytes());
+        let extended = if extended_flags.is_some() { 0x4000 } else { 0 };
+        data.extend((flags | extended | path.len() as u16).to_be_bytes());
+        if let Some(extended_flags) = extended_flags {
+            data.extend(extended_flags.to_be_bytes());
+        }
+        data.extend(path.as_bytes());
+        data.push(0);
+        while !data.len().is_multiple_of(8) {
+            data.push(0);
+        }
+        data
+    }
+
+    fn tracked(path: &Path, name: &str) -> Vec<u8> {
+        let metadata = fs::metadata(path.join(name)).unwrap();
+        let mut data = entry(name, 0, None, 0o100644);
+        data[8..12].copy_from_slice(&(metadata.mtime() as u32).to_be_bytes());
+        data[12..16].copy_from_slice(&(metadata.mtime_nsec() as u32).to_be_bytes());
+        data[36..40].copy_from_slice(&(metadata.size() as u32).to_be_bytes());
+        data
+    }
+
+    fn index(version: u32, entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend(version.to_be_bytes());
+        data.extend((entries.len() as u32).to_be_bytes());
+        entries.iter().for_each(|entry| data.extend(entry));
+        data
+    }
+
+    fn paths(entries: &[IndexEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.path.as_str()).collect()
+    }
+
+    #[test]
+    fn version_two_entries_keep_stat_data() {
+        let data = index(
+            2,
+            &[
+                entry("README.md", 0, None, 0o100644),
+                entry("src/main.rs", 1 << 12, None, 0o100644),
+                entry("unchanged", 0x8000, None, 0o100644),
+                entry("vendor/lib", 0, None, 0o160000),
+            ],
+        );
+        let entries = parse_index(&data, 20).unwrap();
+        assert_eq!(paths(&entries), ["README.md", "src/main.rs"]);
+        assert_eq!(
+            (entries[0].mtime, entries[0].mtime_nsec, entries[0].size),
+            (7, 9, 5)
+        );
+        assert!(!entries[0].conflicted);
+        assert!(entries[1].conflicted);
+    }
+
+    #[test]
+    fn version_three_extended_entries_stay_aligned() {
+        let data = index(
+            3,
+            &[
+                entry("added", 0, Some(0x2000), 0o100644),
+                entry("sparse", 0, Some(0x4000), 0o100644),
+                entry("plain", 0, None, 0o100755),
+            ],
+        );
+        let entries = parse_index(&data, 20).unwrap();
+        assert_eq!(paths(&entries), ["added", "plain"]);
+    }
+
+    #[test]
+    fn other_versions_are_skipped() {
+        let entries = [entry("file", 0, None, 0o100644)];
+        assert!(parse_index(&index(4, &entries), 20).is_none());
+        assert!(parse_index(b"XXXX", 20).is_none());
+    }
+
+    #[test]
+    fn appending_to_a_tracked_file_marks_the_branch_dirty() {
+        let work_tree = std::env::temp_dir().join(format!("git-prompt-{}", std::process::id()));
+        let git_dir = work_tree.join(".git");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(work_tree.join("tracked.txt"), "x\n").unwrap();
+        fs::write(
+            git_dir.join("index"),
+            index(2, &[tracked(&work_tree, "tracked.txt")]),
+        )
+        .unwrap();
+
+        let mut cache = GitCache::new();
+        assert_eq!(cache.segment(&work_tree), " (main)");
+        fs::OpenOptions::new()
+            .append(true)
+            .open(work_tree.join("tracked.txt"))
+            .and_then(|mut file| std::io::Write::write_all(&mut file, b"y\n"))
+            .unwrap();
+        let marker = cache.segment(&work_tree);
+        fs::remove_dir_all(&work_tree).unwrap();
+        assert_eq!(marker, " (main *)");
+    }
+}
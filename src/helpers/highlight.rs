@@ -0,0 +1,57 @@
+use crate::commands::hash::{is_executable, search_path};
+use crate::commands::shell_options::ShellOption;
+use crate::commands::shell_state::ShellState;
+use crate::helpers::completion::expand_tilde;
+use crate::helpers::expand::expand_word;
+use crate::helpers::parser::{BUILTINS, KEYWORDS, SpanKind, token_spans};
+use std::ops::Range;
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const UNDERLINE: &str = "\x1b[4m";
+
+// Styles for the line being edited: commands that would run are green and the rest
+// red, paths that exist are underlined and path-like arguments that don't are red.
+pub fn highlight(line: &str, state: &ShellState) -> Vec<(Range<usize>, &'static str)> {
+    let mut styles = Vec::new();
+    for span in token_spans(line) {
+        let text = &line[span.range.clone()];
+        let style = match span.kind {
+            SpanKind::Command if is_command(text, state) => Some(GREEN),
+            SpanKind::Command => Some(RED),
+            SpanKind::Argument if text.starts_with('-') => None,
+            SpanKind::Argument | SpanKind::Target => match expand_word(text, state) {
+                Ok(word) if !word.is_empty() && expand_tilde(&word).exists() => Some(UNDERLINE),
+                Ok(word) if span.kind == SpanKind::Argument && word.contains('/') => Some(RED),
+                _ => None,
+            },
+            SpanKind::Quoted => Some(YELLOW),
+            SpanKind::Operator => Some(CYAN),
+        };
+        if let Some(style) = style {
+            styles.push((span.range, style));
+        }
+    }
+    styles
+}
+
+fn is_command(text: &str, state: &ShellState) -> bool {
+    let Ok(name) = expand_word(text, state) else {
+        return false;
+    };
+    if BUILTINS.contains(&name.as_str())
+        || KEYWORDS.contains(&name.as_str())
+        || state.aliases.get(&name).is_some()
+    {
+        return true;
+    }
+    if state.options.is_set(ShellOption::Autocd) && expand_tilde(&name).is_dir() {
+        return true;
+    }
+    if name.contains('/') {
+        return is_executable(&expand_tilde(&name));
+    }
+    state.hash.cached(&name).is_some_and(is_executable) || search_path(&name).is_some()
+}
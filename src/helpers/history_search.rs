@@ -0,0 +1,107 @@
+use crate::commands::history::History;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::ops::Range;
+
+pub enum SearchStep {
+    Continue,
+    Run(String),
+    Edit(String),
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistorySearch {
+    query: String,
+    matched: Option<usize>,
+    backward: bool,
+    failed: bool,
+    original: String,
+}
+
+impl HistorySearch {
+    pub fn new(original: &str) -> Self {
+        Self {
+            query: String::new(),
+            matched: None,
+            backward: true,
+            failed: false,
+            original: original.to_string(),
+        }
+    }
+
+    pub fn line<'a>(&'a self, history: &'a History) -> &'a str {
+        self.matched
+            .and_then(|i| history.get(i))
+            .unwrap_or(&self.original)
+    }
+
+    pub fn key(&mut self, key: KeyEvent, history: &History) -> SearchStep {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Char('r') if ctrl => {
+                self.backward = true;
+                let start = self.matched.unwrap_or(history.len());
+                self.search(history, start, true);
+            }
+            KeyCode::Char('s') if ctrl => {
+                self.backward = false;
+                let start = self.matched.map_or(0, |i| i + 1);
+                self.search(history, start, false);
+            }
+            KeyCode::Char('g' | 'c') if ctrl => return SearchStep::Cancel,
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                let start = self.matched.map_or(history.len(), |i| i + 1);
+                self.search(history, start, true);
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.matched = None;
+                self.failed = false;
+                if !self.query.is_empty() {
+                    self.search(history, history.len(), true);
+                }
+            }
+            KeyCode::Enter => return SearchStep::Run(self.line(history).to_string()),
+            _ => return SearchStep::Edit(self.line(history).to_string()),
+        }
+        SearchStep::Continue
+    }
+
+    pub fn label(&self) -> String {
+        let label = match (self.failed, self.backward) {
+            (false, true) => "(reverse-i-search)",
+            (false, false) => "(i-search)",
+            (true, true) => "(failed reverse-i-search)",
+            (true, false) => "(failed i-search)",
+        };
+        format!("{}`{}': ", label, self.query)
+    }
+
+    pub fn highlight(&self, line: &str) -> Option<Range<usize>> {
+        if self.query.is_empty() {
+            return None;
+        }
+        let start = line.find(&self.query)?;
+        Some(start..start + self.query.len())
+    }
+
+    // Searches from `start`, exclusive when walking backward and inclusive when walking forward.
+    fn search(&mut self, history: &History, start: usize, backward: bool) {
+        let query = &self.query;
+        let is_match = |i: &usize| history.get(*i).is_some_and(|line| line.contains(query));
+        let found = if backward {
+            (0..start.min(history.len())).rev().find(is_match)
+        } else {
+            (start..history.len()).find(is_match)
+        };
+        match found {
+            Some(i) => {
+                self.matched = Some(i);
+                self.failed = false;
+            }
+            None => self.failed = !self.query.is_empty(),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+const KILL_RING_SIZE: usize = 16;
+
+pub fn byte_index(line: &str, cursor: usize) -> usize {
+    line.char_indices()
+        .nth(cursor)
+        .map_or(line.len(), |(i, _)| i)
+}
+
+pub fn grapheme_backward(line: &str, cursor: usize) -> usize {
+    let end = byte_index(line, cursor);
+    match line[..end].grapheme_indices(true).next_back() {
+        Some((start, _)) => line[..start].chars().count(),
+        None => 0,
+    }
+}
+
+pub fn grapheme_forward(line: &str, cursor: usize) -> usize {
+    let start = byte_index(line, cursor);
+    match line[start..].graphemes(true).next() {
+        Some(grapheme) => cursor + grapheme.chars().count(),
+        None => cursor,
+    }
+}
+
+// Combining marks ride on their base character; a variation selector asks for emoji width.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    let base = grapheme.chars().next().and_then(|c| c.width()).unwrap_or(0);
+    if grapheme.contains('\u{fe0f}') {
+        base.max(2)
+    } else {
+        base
+    }
+}
+
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+pub fn line_start(text: &str, cursor: usize) -> usize {
+    let end = byte_index(text, cursor);
+    text[..end]
+        .rfind('\n')
+        .map_or(0, |i| text[..=i].chars().count())
+}
+
+pub fn line_end(text: &str, cursor: usize) -> usize {
+    let start = byte_index(text, cursor);
+    match text[start..].find('\n') {
+        Some(i) => cursor + text[start..start + i].chars().count(),
+        None => text.chars().count(),
+    }
+}
+
+// Moves into the line beginning at `start`, as close to display `column` as it allows.
+pub fn cursor_at_column(text: &str, start: usize, column: usize) -> usize {
+    let mut cursor = start;
+    let mut width = 0;
+    for grapheme in text[byte_index(text, start)..].graphemes(true) {
+        let grapheme_width = grapheme_width(grapheme);
+        if grapheme == "\n" || width + grapheme_width > column {
+            break;
+        }
+        width += grapheme_width;
+        cursor += grapheme.chars().count();
+    }
+    cursor
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+pub fn word_backward(line: &str, cursor: usize) -> usize {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = cursor.min(chars.len());
+    while i > 0 && !is_word_char(chars[i - 1]) {
+        i -= 1;
+    }
+    while i > 0 && is_word_char(chars[i - 1]) {
+        i -= 1;
+    }
+    i
+}
+
+pub fn word_forward(line: &str, cursor: usize) -> usize {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = cursor.min(chars.len());
+    while i < chars.len() && !is_word_char(chars[i]) {
+        i += 1;
+    }
+    while i < chars.len() && is_word_char(chars[i]) {
+        i += 1;
+    }
+    i
+}
+
+pub fn blank_word_backward(line: &str, cursor: usize) -> usize {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = cursor.min(chars.len());
+    while i > 0 && chars[i - 1].is_whitespace() {
+        i -= 1;
+    }
+    while i > 0 && !chars[i - 1].is_whitespace() {
+        i -= 1;
+    }
+    i
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KillRing {
+    entries: Vec<String>,
+    index: usize,
+}
+
+impl KillRing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Consecutive kills grow the newest entry instead of pushing a new one.
+    pub fn kill(&mut self, text: String, backward: bool, continuing: bool) {
+        if text.is_empty() {
+            return;
+        }
+        match self.entries.last_mut() {
+            Some(top) if continuing => {
+                if backward {
+                    top.insert_str(0, &text);
+                } else {
+                    top.push_str(&text);
+                }
+            }
+            _ => {
+                self.entries.push(text);
+                if self.entries.len() > KILL_RING_SIZE {
+                    self.entries.remove(0);
+                }
+            }
+        }
+        self.index = self.entries.len().saturating_sub(1);
+    }
+
+    pub fn yank(&mut self) -> Option<&str> {
+        self.index = self.entries.len().checked_sub(1)?;
+        self.entries.last().map(String::as_str)
+    }
+
+    pub fn rotate(&mut self) -> Option<&str> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.index = self.index.checked_sub(1).unwrap_or(self.entries.len() - 1);
+        self.entries.get(self.index).map(String::as_str)
+    }
+}
+
+pub fn remove_range(line: &str, start: usize, end: usize) -> (String, String) {
+    let (start, end) = (byte_index(line, start), byte_index(line, end));
+    let removed = line[start..end].to_string();
+    (format!("{}{}", &line[..start], &line[end..]), removed)
+}
+
+pub fn insert_at(line: &str, cursor: usize, text: &str) -> String {
+    let i = byte_index(line, cursor);
+    format!("{}{}{}", &line[..i], text, &line[i..])
+}
+
+// Swaps the graphemes either side of the cursor, or the last two at the end of the line,
+// and leaves the cursor after both.
+pub fn transpose(line: &str, cursor: usize) -> Option<(String, usize)> {
+    let mut graphemes: Vec<&str> = line.graphemes(true).collect();
+    if graphemes.len() < 2 || cursor == 0 {
+        return None;
+    }
+    let mut at = 0;
+    let mut chars = 0;
+    while at < graphemes.len() && chars < cursor {
+        chars += graphemes[at].chars().count();
+        at += 1;
+    }
+    let at = at.min(graphemes.len() - 1);
+    graphemes.swap(at - 1, at);
+    let cursor = graphemes[..=at].iter().map(|g| g.chars().count()).sum();
+    Some((graphemes.concat(), cursor))
+}
@@ -0,0 +1,1286 @@
+use crate::commands::history::History;
+use crate::commands::shell_options::ShellOption;
+use crate::commands::shell_state::ShellState;
+use crate::helpers::completion::{TabCycle, complete_line, format_columns};
+use crate::helpers::highlight::highlight;
+use crate::helpers::history_search::{HistorySearch, SearchStep};
+use crate::helpers::line_edit::*;
+use crate::helpers::parser::{ParseResult, parse_input};
+use crate::helpers::vi_mode::{ViAction, ViMode, ViState};
+use crossterm::{
+    cursor::{MoveDown, MoveTo, MoveToColumn, MoveUp},
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+use std::collections::HashSet;
+use std::io::{self, Write, stdout};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+const HIGHLIGHT: &str = "\x1b[7m";
+const SUGGESTION: &str = "\x1b[90m";
+const RESET: &str = "\x1b[0m";
+
+pub struct View<'a> {
+    pub prompt: &'a str,
+    pub continuation: &'a str,
+    pub right: &'a str,
+    pub line: &'a str,
+    pub suggestion: &'a str,
+    pub cursor: usize,
+    pub styles: Vec<(Range<usize>, &'a str)>,
+}
+
+pub trait Renderer {
+    fn refresh(&mut self, view: &View) -> io::Result<()>;
+    fn list(&mut self, items: &[String]) -> io::Result<()>;
+    fn clear_screen(&mut self) -> io::Result<()>;
+    fn newline(&mut self) -> io::Result<()>;
+}
+
+struct Drawn {
+    prompt: String,
+    indent: usize,
+    before_cursor: String,
+    rows_below: usize,
+}
+
+// Remembers where the cursor sits inside the last drawn prompt so a redraw can climb
+// back to its first row, even after a resize reflowed it.
+#[derive(Default)]
+pub struct TerminalRenderer {
+    drawn: Option<Drawn>,
+}
+
+impl TerminalRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        if let Some(drawn) = self.drawn.take()
+            && drawn.rows_below > 0
+        {
+            queue!(stdout(), MoveDown(drawn.rows_below as u16))?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn refresh(&mut self, view: &View) -> io::Result<()> {
+        let (width, _) = terminal::size().unwrap_or((80, 24));
+        let width = (width as usize).max(1);
+        let mut out = stdout();
+
+        if let Some(drawn) = &self.drawn {
+            let row = Layout::new(width)
+                .advance(&drawn.prompt, 0)
+                .advance(&drawn.before_cursor, drawn.indent)
+                .row;
+            if row > 0 {
+                queue!(out, MoveUp(row as u16))?;
+            }
+        }
+
+        let line = style_line(view.line, &view.styles, &strip_markers(view.continuation));
+        queue!(
+            out,
+            MoveToColumn(0),
+            Clear(ClearType::FromCursorDown),
+            Print(strip_markers(view.prompt).replace('\n', "\r\n")),
+        )?;
+
+        let prompt = printable(view.prompt);
+        let indent = display_width(&printable(view.continuation));
+        let before_cursor = &view.line[..byte_index(view.line, view.cursor)];
+        let shown = format!("{}{}", view.line, view.suggestion);
+        let start = Layout::new(width).advance(&prompt, 0);
+        if let Some(column) = right_column(start, &shown, view.right) {
+            queue!(
+                out,
+                MoveToColumn(column as u16),
+                Print(strip_markers(view.right)),
+                MoveToColumn(start.column as u16)
+            )?;
+        }
+        queue!(out, Print(line))?;
+        if !view.suggestion.is_empty() {
+            queue!(
+                out,
+                Print(format!("{SUGGESTION}{}{RESET}", view.suggestion))
+            )?;
+        }
+        let end = start.advance(&shown, indent);
+        let cursor = start.advance(before_cursor, indent);
+        if end.wrapped {
+            queue!(out, Print("\r\n"))?;
+        }
+        if end.row > cursor.row {
+            queue!(out, MoveUp((end.row - cursor.row) as u16))?;
+        }
+        queue!(out, MoveToColumn(cursor.column as u16))?;
+
+        self.drawn = Some(Drawn {
+            prompt,
+            indent,
+            before_cursor: before_cursor.to_string(),
+            rows_below: end.row - cursor.row,
+        });
+        out.flush()
+    }
+
+    fn list(&mut self, items: &[String]) -> io::Result<()> {
+        self.leave()?;
+        let (width, _) = terminal::size().unwrap_or((80, 24));
+        print!("\r\n{}", format_columns(items, width as usize));
+        stdout().flush()
+    }
+
+    fn clear_screen(&mut self) -> io::Result<()> {
+        self.drawn = None;
+        execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))
+    }
+
+    fn newline(&mut self) -> io::Result<()> {
+        self.leave()?;
+        print!("\r\n");
+        stdout().flush()
+    }
+}
+
+// Tracks where the terminal cursor ends up while drawing text, wrapping wide characters
+// that no longer fit onto the next row. `wrapped` means the row was filled exactly and
+// the terminal is still holding the cursor in its last column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Layout {
+    width: usize,
+    row: usize,
+    column: usize,
+    wrapped: bool,
+}
+
+impl Layout {
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            row: 0,
+            column: 0,
+            wrapped: false,
+        }
+    }
+
+    // Each newline in `text` starts a row behind a continuation prompt `indent` columns wide.
+    fn advance(mut self, text: &str, indent: usize) -> Self {
+        for grapheme in text.graphemes(true) {
+            if grapheme == "\n" {
+                if !self.wrapped {
+                    self.row += 1;
+                }
+                self.column = 0;
+                self.wrapped = false;
+                self.put(indent);
+                continue;
+            }
+            let grapheme_width = grapheme_width(grapheme);
+            if self.column + grapheme_width > self.width {
+                self.row += 1;
+                self.column = 0;
+            }
+            self.wrapped = false;
+            self.put(grapheme_width);
+        }
+        self
+    }
+
+    fn put(&mut self, columns: usize) {
+        self.column += columns;
+        while self.column >= self.width && columns > 0 {
+            self.row += 1;
+            self.column -= self.width;
+            self.wrapped = self.column == 0;
+        }
+    }
+}
+
+// The right prompt sits at the end of the row the input starts on, and only while the
+// first line of input stays clear of it by at least one column.
+fn right_column(start: Layout, line: &str, right: &str) -> Option<usize> {
+    let right = printable(right);
+    if right.is_empty() || right.contains('\n') {
+        return None;
+    }
+    let first_line = line.split('\n').next().unwrap_or("");
+    let end = start.advance(first_line, 0);
+    let column = start.width.checked_sub(display_width(&right))?;
+    (end.row == start.row && !end.wrapped && end.column < column).then_some(column)
+}
+
+// Applies every style covering each stretch of the line, and closes them around the
+// continuation prompts so neither one's colours leak into the other.
+fn style_line(line: &str, styles: &[(Range<usize>, &str)], continuation: &str) -> String {
+    let mut bounds = vec![0, line.len()];
+    for (range, _) in styles {
+        bounds.extend([range.start, range.end]);
+    }
+    for (i, _) in line.match_indices('\n') {
+        bounds.extend([i, i + 1]);
+    }
+    bounds.retain(|&bound| bound <= line.len());
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut styled = String::new();
+    let mut active = String::new();
+    for pair in bounds.windows(2) {
+        let segment = &line[pair[0]..pair[1]];
+        let style: String = if segment == "\n" {
+            String::new()
+        } else {
+            styles
+                .iter()
+                .filter(|(range, _)| range.contains(&pair[0]))
+                .map(|(_, style)| *style)
+                .collect()
+        };
+        if style != active {
+            if !active.is_empty() {
+                styled.push_str(RESET);
+            }
+            styled.push_str(&style);
+            active = style;
+        }
+        match segment {
+            "\n" => {
+                styled.push_str("\r\n");
+                styled.push_str(continuation);
+            }
+            _ => styled.push_str(segment),
+        }
+    }
+    if !active.is_empty() {
+        styled.push_str(RESET);
+    }
+    styled
+}
+
+// Prompt text between \x01 and \x02 is drawn but takes up no columns, as in readline.
+fn strip_markers(prompt: &str) -> String {
+    prompt.replace(['\x01', '\x02'], "")
+}
+
+// Keeps only what occupies columns: drops marked regions, escape sequences and
+// other control characters, but keeps newlines.
+pub fn printable(text: &str) -> String {
+    let mut shown = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x01' => {
+                for c in chars.by_ref() {
+                    if c == '\x02' {
+                        break;
+                    }
+                }
+            }
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' => shown.push(c),
+            c if c.is_control() => {}
+            c => shown.push(c),
+        }
+    }
+    shown
+}
+
+pub enum Signal {
+    Accept(String),
+    Interrupt(String),
+    Eof,
+}
+
+enum Finish {
+    Accept,
+    Interrupt,
+    Eof,
+}
+
+// Up and Down walk the entries that extend what was typed before the walk began, visiting
+// each distinct line only at its newest position. Stops are found lazily, newest first, and
+// kept so that walking back down doesn't rescan.
+#[derive(Default)]
+struct HistoryWalk {
+    prefix: String,
+    stops: Vec<usize>,
+    seen: HashSet<String>,
+    scanned: usize,
+    position: Option<usize>,
+}
+
+impl HistoryWalk {
+    fn begin(&mut self, prefix: &str, len: usize) {
+        *self = Self {
+            prefix: prefix.to_string(),
+            scanned: len,
+            ..Self::default()
+        };
+    }
+
+    fn older(&mut self, history: &History) -> Option<usize> {
+        let next = self.position.map_or(0, |position| position + 1);
+        while self.stops.len() <= next && self.scanned > 0 {
+            self.scanned -= 1;
+            if let Some(line) = history.get(self.scanned)
+                && line.starts_with(&self.prefix)
+                && line != self.prefix
+                && self.seen.insert(line.to_string())
+            {
+                self.stops.push(self.scanned);
+            }
+        }
+        let index = *self.stops.get(next)?;
+        self.position = Some(next);
+        Some(index)
+    }
+
+    // None once the walk is back at the typed line.
+    fn newer(&mut self) -> Option<usize> {
+        self.position = self.position?.checked_sub(1);
+        self.position.map(|position| self.stops[position])
+    }
+}
+
+pub struct LineEditor {
+    prompt: String,
+    continuation: String,
+    right: String,
+    line: String,
+    cursor: usize,
+    history_index: usize,
+    history_walk: HistoryWalk,
+    recalled: bool,
+    tab_cycle: Option<TabCycle>,
+    kill_ring: KillRing,
+    last_was_kill: bool,
+    last_yank: Option<(usize, usize)>,
+    vi: ViState,
+    vi_enabled: bool,
+    search: Option<HistorySearch>,
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self {
+            prompt: String::new(),
+            continuation: String::new(),
+            right: String::new(),
+            line: String::new(),
+            cursor: 0,
+            history_index: 0,
+            history_walk: HistoryWalk::default(),
+            recalled: false,
+            tab_cycle: None,
+            kill_ring: KillRing::new(),
+            last_was_kill: false,
+            last_yank: None,
+            vi: ViState::new(),
+            vi_enabled: false,
+            search: None,
+        }
+    }
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn start(
+        &mut self,
+        prompt: &str,
+        continuation: &str,
+        right: &str,
+        state: &ShellState,
+        renderer: &mut impl Renderer,
+    ) -> io::Result<()> {
+        self.prompt = prompt.to_string();
+        self.continuation = continuation.to_string();
+        self.right = right.to_string();
+        self.line.clear();
+        self.cursor = 0;
+        self.history_index = state.history.len();
+        self.history_walk = HistoryWalk::default();
+        self.recalled = false;
+        self.tab_cycle = None;
+        self.last_was_kill = false;
+        self.last_yank = None;
+        self.search = None;
+        self.vi.reset();
+        self.vi_enabled = state.options.is_set(ShellOption::Vi);
+        self.refresh(state, renderer)
+    }
+
+    pub fn redraw(&self, state: &ShellState, renderer: &mut impl Renderer) -> io::Result<()> {
+        self.refresh(state, renderer)
+    }
+
+    pub fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        state: &ShellState,
+        renderer: &mut impl Renderer,
+    ) -> io::Result<Option<Signal>> {
+        let finish = match self.search.take() {
+            Some(search) => self.search_key(search, key, &state.history),
+            None => self.edit_key(key, state, renderer)?,
+        };
+        let Some(finish) = finish else {
+            self.refresh(state, renderer)?;
+            return Ok(None);
+        };
+
+        self.cursor = self.line.chars().count();
+        self.draw(state, renderer, false)?;
+        renderer.newline()?;
+        let line = std::mem::take(&mut self.line);
+        self.cursor = 0;
+        Ok(Some(match finish {
+            Finish::Accept => Signal::Accept(line),
+            Finish::Interrupt => Signal::Interrupt(line),
+            Finish::Eof => Signal::Eof,
+        }))
+    }
+
+    // Pasted text lands in the buffer as typed, newlines included, and waits for Enter.
+    pub fn paste(
+        &mut self,
+        text: &str,
+        state: &ShellState,
+        renderer: &mut impl Renderer,
+    ) -> io::Result<()> {
+        if let Some(search) = self.search.take() {
+            self.set_line(search.line(&state.history).to_string());
+        }
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let text = text.trim_end_matches('\n');
+        self.tab_cycle = None;
+        self.last_was_kill = false;
+        self.last_yank = None;
+        self.recalled = false;
+        self.line = insert_at(&self.line, self.cursor, text);
+        self.cursor += text.chars().count();
+        self.refresh(state, renderer)
+    }
+
+    fn search_key(
+        &mut self,
+        mut search: HistorySearch,
+        key: KeyEvent,
+        history: &History,
+    ) -> Option<Finish> {
+        match search.key(key, history) {
+            SearchStep::Continue => self.search = Some(search),
+            SearchStep::Cancel => {}
+            SearchStep::Edit(line) => self.set_line(line),
+            SearchStep::Run(line) => {
+                self.set_line(line);
+                return Some(Finish::Accept);
+            }
+        }
+        None
+    }
+
+    fn edit_key(
+        &mut self,
+        key: KeyEvent,
+        state: &ShellState,
+        renderer: &mut impl Renderer,
+    ) -> io::Result<Option<Finish>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let mut code = key.code;
+
+        if code != KeyCode::Tab {
+            self.tab_cycle = None;
+        }
+        if ctrl && code == KeyCode::Char('r') {
+            self.search = Some(HistorySearch::new(&self.line));
+            return Ok(None);
+        }
+
+        if self.vi_enabled && !ctrl && !alt {
+            let before = self.vi.mode;
+            let action = match (before, code) {
+                (ViMode::Insert, KeyCode::Esc) => {
+                    let (line, cursor) = self.vi.escape(&self.line, self.cursor);
+                    ViAction::Edit(line, cursor)
+                }
+                (ViMode::Insert, KeyCode::Char(c)) => {
+                    self.vi.record(c);
+                    ViAction::None
+                }
+                (ViMode::Insert, KeyCode::Backspace) => {
+                    self.vi.record('\x08');
+                    ViAction::None
+                }
+                (ViMode::Normal, KeyCode::Char(c)) => {
+                    self.vi.normal_key(c, &self.line, self.cursor)
+                }
+                _ => ViAction::None,
+            };
+            let intercepted =
+                before == ViMode::Normal && matches!(code, KeyCode::Char(_) | KeyCode::Esc);
+            match action {
+                ViAction::Edit(line, cursor) => {
+                    self.line = line;
+                    self.cursor = cursor;
+                    return Ok(None);
+                }
+                ViAction::HistoryPrev => code = KeyCode::Up,
+                ViAction::HistoryNext => code = KeyCode::Down,
+                ViAction::None if intercepted => return Ok(None),
+                ViAction::None => {}
+            }
+        }
+
+        let len = self.line.chars().count();
+        let cursor = self.cursor;
+        let start = line_start(&self.line, cursor);
+        let end = line_end(&self.line, cursor);
+        let continuing = std::mem::take(&mut self.last_was_kill);
+        let yanked = self.last_yank.take();
+        let recalled = std::mem::take(&mut self.recalled);
+
+        if let Some(suggestion) = self.suggestion(state) {
+            let accepted = match code {
+                KeyCode::Right | KeyCode::End => Some(suggestion.len()),
+                KeyCode::Char('f' | 'e') if ctrl => Some(suggestion.len()),
+                KeyCode::Char('f') if alt => {
+                    let full = format!("{}{}", self.line, suggestion);
+                    Some(byte_index(&full, word_forward(&full, cursor)) - self.line.len())
+                }
+                _ => None,
+            };
+            if let Some(accepted) = accepted {
+                self.line.push_str(&suggestion[..accepted]);
+                self.cursor = self.line.chars().count();
+                return Ok(None);
+            }
+        }
+
+        match code {
+            KeyCode::Enter => {
+                if !matches!(
+                    parse_input(&self.line, &state.aliases),
+                    ParseResult::Incomplete
+                ) {
+                    return Ok(Some(Finish::Accept));
+                }
+                self.line.push('\n');
+                self.cursor = len + 1;
+            }
+            KeyCode::Char('c') if ctrl => return Ok(Some(Finish::Interrupt)),
+            KeyCode::Char('d') if ctrl && self.line.is_empty() => return Ok(Some(Finish::Eof)),
+            KeyCode::Home => self.cursor = start,
+            KeyCode::End => self.cursor = end,
+            KeyCode::Char('a') if ctrl => self.cursor = start,
+            KeyCode::Char('e') if ctrl => self.cursor = end,
+            KeyCode::Left => self.cursor = grapheme_backward(&self.line, cursor),
+            KeyCode::Right => self.cursor = grapheme_forward(&self.line, cursor),
+            KeyCode::Char('b') if ctrl => self.cursor = grapheme_backward(&self.line, cursor),
+            KeyCode::Char('f') if ctrl => self.cursor = grapheme_forward(&self.line, cursor),
+            KeyCode::Char('b') if alt => self.cursor = word_backward(&self.line, cursor),
+            KeyCode::Char('f') if alt => self.cursor = word_forward(&self.line, cursor),
+            KeyCode::Delete => self.delete(cursor, grapheme_forward(&self.line, cursor)),
+            KeyCode::Char('d') if ctrl => self.delete(cursor, grapheme_forward(&self.line, cursor)),
+            KeyCode::Backspace => self.delete(grapheme_backward(&self.line, cursor), cursor),
+            KeyCode::Char('k') if ctrl => self.kill(cursor, end, false, continuing),
+            KeyCode::Char('u') if ctrl => self.kill(start, cursor, true, continuing),
+            KeyCode::Char('w') if ctrl => {
+                let start = blank_word_backward(&self.line, cursor);
+                self.kill(start, cursor, true, continuing);
+            }
+            KeyCode::Char('d') if alt => {
+                let end = word_forward(&self.line, cursor);
+                self.kill(cursor, end, false, continuing);
+            }
+            KeyCode::Char('y') if ctrl => {
+                if let Some(text) = self.kill_ring.yank() {
+                    let text = text.to_string();
+                    self.yank(cursor, &text);
+                }
+            }
+            KeyCode::Char('y') if alt => {
+                if let Some((start, end)) = yanked
+                    && let Some(text) = self.kill_ring.rotate()
+                {
+                    let text = text.to_string();
+                    self.line = remove_range(&self.line, start, end).0;
+                    self.yank(start, &text);
+                }
+            }
+            KeyCode::Char('t') if ctrl => {
+                if let Some((line, cursor)) = transpose(&self.line, cursor) {
+                    self.line = line;
+                    self.cursor = cursor;
+                }
+            }
+            KeyCode::Char('l') if ctrl => renderer.clear_screen()?,
+            KeyCode::Char(_) if ctrl || alt => {}
+            KeyCode::Char(c) => {
+                self.line = insert_at(&self.line, cursor, c.encode_utf8(&mut [0; 4]));
+                self.cursor = cursor + 1;
+            }
+            KeyCode::Up if start > 0 && !recalled => {
+                let above = line_start(&self.line, start - 1);
+                self.cursor = cursor_at_column(&self.line, above, self.column());
+            }
+            KeyCode::Down if end < len && !recalled => {
+                self.cursor = cursor_at_column(&self.line, end + 1, self.column());
+            }
+            KeyCode::Up => {
+                if self.history_index == state.history.len() {
+                    self.history_walk.begin(&self.line, state.history.len());
+                }
+                match self.history_walk.older(&state.history) {
+                    Some(index) => {
+                        self.history_index = index;
+                        self.recall(&state.history);
+                    }
+                    None => self.recalled = recalled,
+                }
+            }
+            KeyCode::Down if self.history_index < state.history.len() => {
+                self.history_index = self.history_walk.newer().unwrap_or(state.history.len());
+                self.recall(&state.history);
+            }
+            KeyCode::Tab => {
+                let (from, to) = (byte_index(&self.line, start), byte_index(&self.line, end));
+                let current = &self.line[from..to];
+                let byte = byte_index(current, cursor - start);
+                let result = complete_line(current, byte, state, &mut self.tab_cycle);
+                if let Some(listing) = result.listing {
+                    renderer.list(&listing)?;
+                }
+                self.cursor = start + result.line[..result.cursor].chars().count();
+                self.line.replace_range(from..to, &result.line);
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn set_line(&mut self, line: String) {
+        self.cursor = line.chars().count();
+        self.line = line;
+    }
+
+    // A freshly recalled entry keeps Up and Down walking history instead of its lines.
+    fn recall(&mut self, history: &History) {
+        let entry = history
+            .get(self.history_index)
+            .unwrap_or(&self.history_walk.prefix)
+            .to_string();
+        self.set_line(entry);
+        self.recalled = true;
+    }
+
+    fn column(&self) -> usize {
+        let start = byte_index(&self.line, line_start(&self.line, self.cursor));
+        display_width(&self.line[start..byte_index(&self.line, self.cursor)])
+    }
+
+    fn delete(&mut self, start: usize, end: usize) {
+        self.line = remove_range(&self.line, start, end).0;
+        self.cursor = start;
+    }
+
+    fn kill(&mut self, start: usize, end: usize, backward: bool, continuing: bool) {
+        let (line, removed) = remove_range(&self.line, start, end);
+        self.kill_ring.kill(removed, backward, continuing);
+        self.last_was_kill = true;
+        self.line = line;
+        self.cursor = start;
+    }
+
+    fn yank(&mut self, at: usize, text: &str) {
+        let end = at + text.chars().count();
+        self.line = insert_at(&self.line, at, text);
+        self.cursor = end;
+        self.last_yank = Some((at, end));
+    }
+
+    // Completes the line from history while the cursor sits at its end.
+    fn suggestion<'a>(&self, state: &'a ShellState) -> Option<&'a str> {
+        if self.search.is_some()
+            || (self.vi_enabled && self.vi.mode == ViMode::Normal)
+            || self.cursor != self.line.chars().count()
+        {
+            return None;
+        }
+        state
+            .history
+            .suggest(&self.line, &state.pwd.get_current_dir())
+    }
+
+    fn refresh(&self, state: &ShellState, renderer: &mut impl Renderer) -> io::Result<()> {
+        self.draw(state, renderer, true)
+    }
+
+    // Accepted lines are drawn one last time without their suggestion.
+    fn draw(
+        &self,
+        state: &ShellState,
+        renderer: &mut impl Renderer,
+        suggest: bool,
+    ) -> io::Result<()> {
+        if let Some(search) = &self.search {
+            let prompt = search.label();
+            let line = search.line(&state.history);
+            let highlight = search.highlight(line);
+            let cursor = highlight.as_ref().map_or(line.chars().count(), |range| {
+                line[..range.start].chars().count()
+            });
+            return renderer.refresh(&View {
+                prompt: &prompt,
+                continuation: &self.continuation,
+                right: "",
+                line,
+                suggestion: "",
+                cursor,
+                styles: highlight
+                    .into_iter()
+                    .map(|range| (range, HIGHLIGHT))
+                    .collect(),
+            });
+        }
+
+        let indicator = if self.vi_enabled {
+            self.vi.mode.indicator()
+        } else {
+            ""
+        };
+        let prompt = format!("{}{}", indicator, self.prompt);
+        renderer.refresh(&View {
+            prompt: &prompt,
+            continuation: &self.continuation,
+            right: &self.right,
+            line: &self.line,
+            suggestion: self.suggestion(state).filter(|_| suggest).unwrap_or(""),
+            cursor: self.cursor,
+            styles: highlight(&self.line, state),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pwd_state::PwdState;
+
+    #[derive(Default)]
+    struct TestRenderer {
+        screen: String,
+        suggestion: String,
+        listings: Vec<Vec<String>>,
+    }
+
+    impl Renderer for TestRenderer {
+        fn refresh(&mut self, view: &View) -> io::Result<()> {
+            self.screen = format!("{}{}", view.prompt, view.line);
+            self.suggestion = view.suggestion.to_string();
+            Ok(())
+        }
+
+        fn list(&mut self, items: &[String]) -> io::Result<()> {
+            self.listings.push(items.to_vec());
+            Ok(())
+        }
+
+        fn clear_screen(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn newline(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Harness {
+        editor: LineEditor,
+        state: ShellState,
+        renderer: TestRenderer,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let state = ShellState::new(PwdState::new("/".to_string(), "/".to_string()));
+            let mut harness = Self {
+                editor: LineEditor::new(),
+                state,
+                renderer: TestRenderer::default(),
+            };
+            harness.start();
+            harness
+        }
+
+        fn start(&mut self) {
+            self.editor
+                .start("$ ", "> ", "", &self.state, &mut self.renderer)
+                .unwrap();
+        }
+
+        fn press(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Option<Signal> {
+            let key = KeyEvent::new(code, modifiers);
+            self.editor
+                .handle_key(key, &self.state, &mut self.renderer)
+                .unwrap()
+        }
+
+        fn key(&mut self, code: KeyCode) -> Option<Signal> {
+            self.press(code, KeyModifiers::NONE)
+        }
+
+        fn ctrl(&mut self, c: char) -> Option<Signal> {
+            self.press(KeyCode::Char(c), KeyModifiers::CONTROL)
+        }
+
+        fn alt(&mut self, c: char) -> Option<Signal> {
+            self.press(KeyCode::Char(c), KeyModifiers::ALT)
+        }
+
+        fn type_str(&mut self, text: &str) {
+            for c in text.chars() {
+                self.key(KeyCode::Char(c));
+            }
+        }
+
+        fn state(&self) -> (&str, usize) {
+            (self.editor.line(), self.editor.cursor())
+        }
+    }
+
+    #[test]
+    fn inserts_and_moves() {
+        let mut h = Harness::new();
+        h.type_str("echo hi");
+        assert_eq!(h.state(), ("echo hi", 7));
+        h.key(KeyCode::Left);
+        h.key(KeyCode::Left);
+        h.type_str("X");
+        assert_eq!(h.state(), ("echo Xhi", 6));
+        h.ctrl('a');
+        assert_eq!(h.state(), ("echo Xhi", 0));
+        h.key(KeyCode::Left);
+        assert_eq!(h.editor.cursor(), 0);
+        h.ctrl('e');
+        h.key(KeyCode::Right);
+        assert_eq!(h.editor.cursor(), 8);
+        assert_eq!(h.renderer.screen, "$ echo Xhi");
+    }
+
+    #[test]
+    fn deletes_around_cursor() {
+        let mut h = Harness::new();
+        h.type_str("abcd");
+        h.key(KeyCode::Backspace);
+        assert_eq!(h.state(), ("abc", 3));
+        h.key(KeyCode::Home);
+        h.key(KeyCode::Delete);
+        assert_eq!(h.state(), ("bc", 0));
+        h.ctrl('d');
+        assert_eq!(h.state(), ("c", 0));
+        h.key(KeyCode::Backspace);
+        assert_eq!(h.state(), ("c", 0));
+    }
+
+    #[test]
+    fn handles_multibyte_characters() {
+        let mut h = Harness::new();
+        h.type_str("héllo");
+        h.key(KeyCode::Left);
+        h.key(KeyCode::Left);
+        h.key(KeyCode::Left);
+        h.key(KeyCode::Backspace);
+        assert_eq!(h.state(), ("hllo", 1));
+    }
+
+    #[test]
+    fn word_motions() {
+        let mut h = Harness::new();
+        h.type_str("git commit -m msg");
+        h.alt('b');
+        assert_eq!(h.editor.cursor(), 14);
+        h.alt('b');
+        h.alt('b');
+        assert_eq!(h.editor.cursor(), 4);
+        h.alt('f');
+        assert_eq!(h.editor.cursor(), 10);
+    }
+
+    #[test]
+    fn kill_and_yank() {
+        let mut h = Harness::new();
+        h.type_str("one two three");
+        h.ctrl('w');
+        h.ctrl('w');
+        assert_eq!(h.state(), ("one ", 4));
+        h.ctrl('y');
+        assert_eq!(h.state(), ("one two three", 13));
+        h.ctrl('a');
+        h.ctrl('k');
+        assert_eq!(h.state(), ("", 0));
+        h.ctrl('y');
+        h.alt('y');
+        assert_eq!(h.state(), ("two three", 9));
+    }
+
+    #[test]
+    fn transposes_characters() {
+        let mut h = Harness::new();
+        h.type_str("sl");
+        h.ctrl('t');
+        assert_eq!(h.state(), ("ls", 2));
+        h.ctrl('u');
+        h.type_str("ae\u{301}b");
+        h.ctrl('t');
+        assert_eq!(h.state(), ("abe\u{301}", 4));
+        h.key(KeyCode::Home);
+        h.key(KeyCode::Right);
+        h.ctrl('t');
+        assert_eq!(h.state(), ("bae\u{301}", 2));
+        h.ctrl('t');
+        assert_eq!(h.state(), ("be\u{301}a", 4));
+    }
+
+    #[test]
+    fn enter_accepts_and_resets() {
+        let mut h = Harness::new();
+        h.type_str("ls");
+        h.key(KeyCode::Left);
+        match h.key(KeyCode::Enter) {
+            Some(Signal::Accept(line)) => assert_eq!(line, "ls"),
+            _ => panic!("expected accept"),
+        }
+        assert_eq!(h.state(), ("", 0));
+    }
+
+    #[test]
+    fn ctrl_c_interrupts_and_ctrl_d_ends_input() {
+        let mut h = Harness::new();
+        h.type_str("partial");
+        assert!(matches!(h.ctrl('c'), Some(Signal::Interrupt(line)) if line == "partial"));
+        h.start();
+        assert!(matches!(h.ctrl('d'), Some(Signal::Eof)));
+    }
+
+    #[test]
+    fn history_navigation() {
+        let mut h = Harness::new();
+        h.state.history.add("first", "/");
+        h.state.history.add("second", "/");
+        h.start();
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("second", 6));
+        h.key(KeyCode::Up);
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("first", 5));
+        h.key(KeyCode::Down);
+        h.key(KeyCode::Down);
+        assert_eq!(h.state(), ("", 0));
+    }
+
+    #[test]
+    fn history_navigation_filters_by_typed_prefix() {
+        let mut h = Harness::new();
+        for line in ["git pull", "ls", "git push", "git pull", "make", "git"] {
+            h.state.history.add(line, "/");
+        }
+        h.start();
+        h.type_str("git p");
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("git pull", 8));
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("git push", 8));
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("git push", 8));
+        h.key(KeyCode::Down);
+        assert_eq!(h.state(), ("git pull", 8));
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("git push", 8));
+        h.key(KeyCode::Down);
+        h.key(KeyCode::Down);
+        assert_eq!(h.state(), ("git p", 5));
+
+        h.ctrl('u');
+        h.key(KeyCode::Up);
+        h.key(KeyCode::Up);
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("git pull", 8));
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("git push", 8));
+    }
+
+    #[test]
+    fn suggests_from_history_in_this_directory_first() {
+        let mut h = Harness::new();
+        h.state.history.add("git stash pop", "/");
+        h.state.history.add("git status", "/tmp");
+        h.start();
+        h.type_str("git s");
+        assert_eq!(h.renderer.suggestion, "tash pop");
+        h.alt('f');
+        assert_eq!(h.state(), ("git stash", 9));
+        assert_eq!(h.renderer.suggestion, " pop");
+        h.key(KeyCode::Left);
+        assert_eq!(h.renderer.suggestion, "");
+        h.key(KeyCode::End);
+        h.key(KeyCode::Right);
+        assert_eq!(h.state(), ("git stash pop", 13));
+        h.type_str("x");
+        assert_eq!(h.renderer.suggestion, "");
+
+        h.ctrl('u');
+        h.type_str("git stat");
+        assert_eq!(h.renderer.suggestion, "us");
+        h.key(KeyCode::Enter);
+        assert_eq!(h.renderer.suggestion, "");
+    }
+
+    #[test]
+    fn pastes_multiple_lines_without_running_them() {
+        let mut h = Harness::new();
+        h.type_str("echo ");
+        h.editor
+            .paste("one\r\nrm -rf two\n", &h.state, &mut h.renderer)
+            .unwrap();
+        assert_eq!(h.state(), ("echo one\nrm -rf two", 19));
+        assert_eq!(h.renderer.screen, "$ echo one\nrm -rf two");
+        assert!(matches!(
+            h.key(KeyCode::Enter),
+            Some(Signal::Accept(line)) if line == "echo one\nrm -rf two"
+        ));
+    }
+
+    #[test]
+    fn reverse_search() {
+        let mut h = Harness::new();
+        h.state.history.add("make build", "/");
+        h.state.history.add("cargo test", "/");
+        h.state.history.add("make check", "/");
+        h.start();
+        h.ctrl('r');
+        h.type_str("make");
+        assert!(h.renderer.screen.starts_with("(reverse-i-search)`make': "));
+        assert!(h.renderer.screen.ends_with("make check"));
+        h.ctrl('r');
+        assert!(h.renderer.screen.ends_with("make build"));
+        h.key(KeyCode::Right);
+        assert_eq!(h.state(), ("make build", 10));
+        assert_eq!(h.renderer.screen, "$ make build");
+
+        h.ctrl('r');
+        h.type_str("cargo");
+        match h.key(KeyCode::Enter) {
+            Some(Signal::Accept(line)) => assert_eq!(line, "cargo test"),
+            _ => panic!("expected accept"),
+        }
+    }
+
+    #[test]
+    fn tab_completes_builtins() {
+        let mut h = Harness::new();
+        h.type_str("hist");
+        h.key(KeyCode::Tab);
+        assert_eq!(h.state(), ("history ", 8));
+        h.ctrl('u');
+        h.type_str("c");
+        h.key(KeyCode::Tab);
+        assert_eq!(h.renderer.listings.len(), 1);
+        assert!(h.renderer.listings[0].contains(&"cd".to_string()));
+    }
+
+    #[test]
+    fn vi_mode_editing() {
+        let mut h = Harness::new();
+        h.state.options.set(ShellOption::Vi, true);
+        h.start();
+        h.type_str("echo one two");
+        assert_eq!(h.renderer.screen, "(ins) $ echo one two");
+        h.key(KeyCode::Esc);
+        assert_eq!(h.state(), ("echo one two", 11));
+        assert!(h.renderer.screen.starts_with("(cmd) "));
+        h.type_str("bdw");
+        assert_eq!(h.state(), ("echo one ", 8));
+        h.type_str("0.");
+        assert_eq!(h.state(), ("one ", 0));
+        h.type_str("u");
+        assert_eq!(h.state(), ("echo one ", 0));
+        h.type_str("A!");
+        h.key(KeyCode::Esc);
+        assert_eq!(h.state(), ("echo one !", 9));
+    }
+
+    #[test]
+    fn vi_mode_clamps_huge_counts() {
+        let mut h = Harness::new();
+        h.state.options.set(ShellOption::Vi, true);
+        h.start();
+        h.type_str("abc");
+        h.key(KeyCode::Esc);
+        h.type_str("0999999999999999999999l");
+        assert_eq!(h.state(), ("abc", 2));
+        h.type_str("999999999999999999999h");
+        assert_eq!(h.state(), ("abc", 0));
+        h.type_str("x999999999999999999999p");
+        assert_eq!(h.editor.line().len(), 2 + 9999);
+        assert_eq!(h.editor.cursor(), 9999);
+        h.type_str("0l999999999999999999999x");
+        assert_eq!(h.state(), ("bc", 1));
+        h.type_str("099999999d99999999w");
+        assert_eq!(h.state(), ("", 0));
+    }
+
+    #[test]
+    fn prompt_width_ignores_escapes() {
+        assert_eq!(printable("\x1b[38;2;0;180;255m/tmp$ \x1b[0m"), "/tmp$ ");
+        assert_eq!(printable("\x01\x1b]0;title\x07\x02> "), "> ");
+        assert_eq!(printable("\x1b]0;title\x1b\\a\nb\x07"), "a\nb");
+    }
+
+    #[test]
+    fn moves_over_whole_graphemes() {
+        let mut h = Harness::new();
+        h.type_str("ae\u{301}日");
+        assert_eq!(h.editor.cursor(), 4);
+        h.key(KeyCode::Left);
+        assert_eq!(h.editor.cursor(), 3);
+        h.key(KeyCode::Left);
+        assert_eq!(h.editor.cursor(), 1);
+        h.key(KeyCode::Delete);
+        assert_eq!(h.state(), ("a日", 1));
+        h.key(KeyCode::End);
+        h.key(KeyCode::Backspace);
+        assert_eq!(h.state(), ("a", 1));
+    }
+
+    fn position(start: usize, text: &str, width: usize) -> (usize, usize) {
+        let layout = Layout::new(width)
+            .advance(&" ".repeat(start), 0)
+            .advance(text, 2);
+        (layout.row, layout.column)
+    }
+
+    #[test]
+    fn wraps_by_display_width() {
+        assert_eq!(position(2, "abc", 10), (0, 5));
+        assert_eq!(position(2, "abcdefgh", 10), (1, 0));
+        assert_eq!(position(8, "a日本", 10), (1, 4));
+        assert_eq!(position(0, "e\u{301}日", 10), (0, 3));
+        assert_eq!(position(25, "", 10), (2, 5));
+        assert_eq!(display_width(&printable("日本$ ")), 6);
+    }
+
+    #[test]
+    fn continuation_lines_start_new_rows() {
+        assert_eq!(position(5, "ab\ncd", 10), (1, 4));
+        assert_eq!(position(5, "abcde\n", 10), (1, 2));
+        assert_eq!(position(5, "abc\n\nd", 10), (2, 3));
+        assert_eq!(position(0, "abcdefghij\nx", 10), (1, 3));
+        let prompt = Layout::new(10).advance("~/src\n$ ", 0);
+        assert_eq!((prompt.row, prompt.column), (1, 2));
+    }
+
+    #[test]
+    fn styles_overlap_and_stop_at_continuation_lines() {
+        let styles = [(0..4, "<c>"), (5..10, "<q>"), (5..12, "<u>")];
+        assert_eq!(
+            style_line("echo 'a\nb' x", &styles, "> "),
+            "<c>echo\x1b[0m <q><u>'a\x1b[0m\r\n> <q><u>b'\x1b[0m<u> x\x1b[0m"
+        );
+        assert_eq!(style_line("ls\nx", &[], "> "), "ls\r\n> x");
+    }
+
+    #[test]
+    fn right_prompt_hides_when_input_reaches_it() {
+        let start = Layout::new(20).advance("$ ", 0);
+        assert_eq!(right_column(start, "ls", "\x1b[31m[1]\x1b[0m"), Some(17));
+        assert_eq!(right_column(start, "echo hello w", "[1]"), Some(17));
+        assert_eq!(right_column(start, "echo hello wo", "[1]"), Some(17));
+        assert_eq!(right_column(start, "echo hello wor", "[1]"), Some(17));
+        assert_eq!(right_column(start, "echo hello worl", "[1]"), None);
+        assert_eq!(right_column(start, "x\necho hello world!", "[1]"), Some(17));
+        assert_eq!(right_column(start, "ls", ""), None);
+        let start = Layout::new(20).advance("a long prompt here ", 0);
+        assert_eq!(right_column(start, "", "[1]"), None);
+    }
+
+    #[test]
+    fn incomplete_input_continues_on_a_new_line() {
+        let mut h = Harness::new();
+        h.type_str("echo 'one");
+        assert!(h.key(KeyCode::Enter).is_none());
+        h.type_str("two'");
+        assert_eq!(h.state(), ("echo 'one\ntwo'", 14));
+        assert_eq!(h.renderer.screen, "$ echo 'one\ntwo'");
+
+        h.key(KeyCode::Up);
+        assert_eq!(h.editor.cursor(), 4);
+        h.key(KeyCode::Backspace);
+        h.ctrl('e');
+        h.type_str("!");
+        assert_eq!(h.state(), ("ech 'one!\ntwo'", 9));
+        h.key(KeyCode::Down);
+        assert_eq!(h.editor.cursor(), 14);
+        h.ctrl('u');
+        assert_eq!(h.state(), ("ech 'one!\n", 10));
+        h.type_str("x'");
+        match h.key(KeyCode::Enter) {
+            Some(Signal::Accept(line)) => assert_eq!(line, "ech 'one!\nx'"),
+            _ => panic!("expected accept"),
+        }
+    }
+
+    #[test]
+    fn recalls_multi_line_entries_as_a_unit() {
+        let mut h = Harness::new();
+        h.state.history.add("first", "/");
+        h.state.history.add("echo 'a\nb'", "/");
+        h.state.history.add("last", "/");
+        h.start();
+        h.key(KeyCode::Up);
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("echo 'a\nb'", 10));
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("first", 5));
+        h.key(KeyCode::Down);
+        h.key(KeyCode::Left);
+        h.key(KeyCode::Up);
+        assert_eq!(h.state(), ("echo 'a\nb'", 1));
+    }
+}
@@ -1,3 +1,12 @@
-pub mod parser;
+pub mod completion;
 pub mod executor;
-pub mod welcome;
\ No newline at end of file
+pub mod expand;
+pub mod git_prompt;
+pub mod highlight;
+pub mod history_search;
+pub mod line_edit;
+pub mod line_editor;
+pub mod parser;
+pub mod prompt;
+pub mod vi_mode;
+pub mod welcome;
@@ -1,189 +1,572 @@
 use super::executor::*;
-use crate::commands::pwd_state::*;
+use super::expand::split_assignment;
+use crate::commands::alias::Aliases;
+use crate::commands::shell_options::ShellOption;
+use crate::commands::shell_state::ShellState;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::CharIndices;
 
 #[derive(Debug)]
 pub enum CommandEnum {
     Rm(Vec<String>),
     Cp(Vec<String>),
     Mv(Vec<String>),
-    Pwd,
+    Pwd(Vec<String>),
     Cd(Vec<String>, Vec<String>),
     Echo(Vec<String>),
     Mkdir(Vec<String>, Vec<String>),
     Exit,
-    Unknown(String),
+    Unknown(Vec<String>),
     Cat(Vec<String>),
     Ls(Vec<String>),
+    Set(Vec<String>),
+    Test(Vec<String>, bool),
+    Read(Vec<String>),
+    Printf(Vec<String>),
+    Type(Vec<String>),
+    Which(Vec<String>),
+    Command(Vec<String>),
+    Hash(Vec<String>),
+    Alias(Vec<String>),
+    Unalias(Vec<String>),
+    History(Vec<String>),
+    Complete(Vec<String>),
+    Compgen(Vec<String>),
+    Pushd(Vec<String>),
+    Popd(Vec<String>),
+    Dirs(Vec<String>),
+    Z(Vec<String>),
+    Clear,
+}
+
+pub const BUILTINS: &[&str] = &[
+    "[", "alias", "cat", "cd", "clear", "command", "compgen", "complete", "cp", "dirs", "echo",
+    "exit", "hash", "history", "j", "ls", "mkdir", "mv", "popd", "printf", "pushd", "pwd", "read",
+    "rm", "set", "test", "type", "unalias", "which", "z",
+];
+
+pub const KEYWORDS: &[&str] = &["[[", "]]"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connector {
+    Seq,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedirectKind {
+    Truncate,
+    Append,
+    Clobber,
+}
+
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    pub kind: RedirectKind,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimpleCommand {
+    pub words: Vec<String>,
+    pub redirects: Vec<Redirect>,
+}
+
+impl SimpleCommand {
+    fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.redirects.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    pub connector: Connector,
+    pub commands: Vec<SimpleCommand>,
 }
 
 #[derive(Debug)]
 pub enum ParseResult {
-    Ok(Vec<CommandEnum>),
+    Ok(Vec<Pipeline>),
     Incomplete,
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    And,
+    Or,
+    Pipe,
+    Semi,
+    Redirect(RedirectKind),
+}
+
+impl Token {
+    fn text(&self) -> &str {
+        match self {
+            Token::Word(w) => w,
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Pipe => "|",
+            Token::Semi => ";",
+            Token::Redirect(RedirectKind::Truncate) => ">",
+            Token::Redirect(RedirectKind::Append) => ">>",
+            Token::Redirect(RedirectKind::Clobber) => ">|",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Normal,
+    Single,
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanKind {
+    Command,
+    Argument,
+    Target,
+    Quoted,
+    Operator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub kind: SpanKind,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum LexKind {
+    Word,
+    Quote,
+    Operator(Token),
+    ConditionalOperator,
+    Newline,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Lexeme {
+    kind: LexKind,
+    range: Range<usize>,
 }
 
-fn parse_tokens(input: &str) -> Result<Vec<Vec<String>>, String> {
-    let mut commands: Vec<Vec<String>> = Vec::new();
-    let mut current_args: Vec<String> = Vec::new();
-    let mut current_token = String::new();
+struct Lexer<'a> {
+    input: &'a str,
+    lexemes: Vec<Lexeme>,
+    word: Option<(usize, usize)>,
+    command_start: bool,
+    in_conditional: bool,
+}
 
-    #[derive(Clone, Copy, PartialEq)]
-    enum Mode {
-        Normal,
-        Single,
-        Double,
+impl Lexer<'_> {
+    // A pending `]]` closes the conditional before the next operator is classified.
+    fn in_conditional_at(&self, end: usize) -> bool {
+        self.in_conditional
+            && self
+                .word
+                .is_none_or(|(start, _)| &self.input[start..end] != "]]")
     }
 
+    // Words are inserted ahead of the quotes found inside them.
+    fn flush(&mut self, end: usize) {
+        let Some((start, index)) = self.word.take() else {
+            return;
+        };
+        let text = &self.input[start..end];
+        if text == "[[" && self.command_start {
+            self.in_conditional = true;
+        } else if text == "]]" && self.in_conditional {
+            self.in_conditional = false;
+        }
+        self.command_start = false;
+        self.lexemes.insert(
+            index,
+            Lexeme {
+                kind: LexKind::Word,
+                range: start..end,
+            },
+        );
+    }
+
+    fn push(&mut self, kind: LexKind, range: Range<usize>) {
+        if matches!(
+            kind,
+            LexKind::Newline
+                | LexKind::Operator(Token::And | Token::Or | Token::Pipe | Token::Semi)
+        ) {
+            self.command_start = true;
+        }
+        self.lexemes.push(Lexeme { kind, range });
+    }
+}
+
+fn doubled(next: char, chars: &mut Peekable<CharIndices>) -> bool {
+    chars.next_if(|&(_, c)| c == next).is_some()
+}
+
+// Splits input into words, quotes and operators with their byte ranges. Partial input is
+// still split, and the flag reports whether it ended inside a quote, escape or `[[`.
+fn lex(input: &str) -> (Vec<Lexeme>, bool) {
+    let mut lexer = Lexer {
+        input,
+        lexemes: Vec::new(),
+        word: None,
+        command_start: true,
+        in_conditional: false,
+    };
+    let mut quote_start = 0;
     let mut mode = Mode::Normal;
     let mut escaped = false;
-    let mut chars = input.chars().peekable();
+    let mut chars = input.char_indices().peekable();
 
-    while let Some(c) = chars.next() {
+    while let Some((i, c)) = chars.next() {
         if escaped {
-            match mode {
-                Mode::Normal => {
-                    current_token.push(c);
-                }
-                Mode::Double => {
-                    if c == '"' || c == '\\' {
-                        current_token.push(c);
-                    } else {
-                        current_token.push('\\');
-                        current_token.push(c);
+            escaped = false;
+            continue;
+        }
+
+        match mode {
+            Mode::Normal => {
+                let in_conditional = lexer.in_conditional_at(i);
+                let kind = match c {
+                    '&' | '|' | '>' if in_conditional => {
+                        doubled(c, &mut chars);
+                        Some(LexKind::ConditionalOperator)
                     }
+                    '&' if doubled('&', &mut chars) => Some(LexKind::Operator(Token::And)),
+                    '|' if doubled('|', &mut chars) => Some(LexKind::Operator(Token::Or)),
+                    '|' => Some(LexKind::Operator(Token::Pipe)),
+                    ';' => Some(LexKind::Operator(Token::Semi)),
+                    '\n' if !in_conditional => Some(LexKind::Newline),
+                    '>' => {
+                        let kind = if doubled('>', &mut chars) {
+                            RedirectKind::Append
+                        } else if doubled('|', &mut chars) {
+                            RedirectKind::Clobber
+                        } else {
+                            RedirectKind::Truncate
+                        };
+                        Some(LexKind::Operator(Token::Redirect(kind)))
+                    }
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    let end = chars.peek().map_or(input.len(), |&(j, _)| j);
+                    lexer.flush(i);
+                    lexer.push(kind, i..end);
+                    continue;
                 }
-                Mode::Single => {
-                    current_token.push('\\');
-                    current_token.push(c);
+                if c.is_whitespace() {
+                    lexer.flush(i);
+                    continue;
                 }
-            }
-            escaped = false;
-        } else {
-            match mode {
-                Mode::Normal => {
-                    if c == '\\' {
-                        escaped = true;
-                    } else if c == '\'' {
+                lexer.word.get_or_insert((i, lexer.lexemes.len()));
+                match c {
+                    '\\' => escaped = true,
+                    '\'' => {
                         mode = Mode::Single;
-                    } else if c == '"' {
+                        quote_start = i;
+                    }
+                    '"' => {
                         mode = Mode::Double;
-                    } else if c == '&' {
-                        if let Some(&next_c) = chars.peek() {
-                            if next_c == '&' {
-                                chars.next();
-
-                                if !current_token.is_empty() {
-                                    current_args.push(current_token.clone());
-                                    current_token.clear();
-                                }
-                                if !current_args.is_empty() {
-                                    commands.push(current_args.clone());
-                                    current_args.clear();
-                                }
-                                continue;
-                            }
-                        }
-                        current_token.push(c);
-                    } else if c.is_whitespace() {
-                        if !current_token.is_empty() {
-                            current_args.push(current_token.clone());
-                            current_token.clear();
-                        }
-                    } else {
-                        current_token.push(c);
+                        quote_start = i;
                     }
+                    _ => {}
                 }
-                Mode::Single => {
-                    if c == '\'' {
-                        mode = Mode::Normal;
-                    } else {
-                        current_token.push(c);
-                    }
+            }
+            Mode::Single if c == '\'' => {
+                lexer.push(LexKind::Quote, quote_start..i + 1);
+                mode = Mode::Normal;
+            }
+            Mode::Single => {}
+            Mode::Double => match c {
+                '\\' => escaped = true,
+                '"' => {
+                    lexer.push(LexKind::Quote, quote_start..i + 1);
+                    mode = Mode::Normal;
                 }
-                Mode::Double => {
-                    if c == '\\' {
-                        escaped = true;
-                    } else if c == '"' {
-                        mode = Mode::Normal;
-                    } else {
-                        current_token.push(c);
-                    }
+                _ => {}
+            },
+        }
+    }
+
+    if mode != Mode::Normal {
+        lexer.push(LexKind::Quote, quote_start..input.len());
+    }
+    lexer.flush(input.len());
+    let complete = !escaped && mode == Mode::Normal && !lexer.in_conditional;
+    (lexer.lexemes, complete)
+}
+
+fn parse_tokens(input: &str) -> Result<Vec<Token>, String> {
+    let (lexemes, complete) = lex(input);
+    if !complete {
+        return Err("Incomplete".to_string());
+    }
+
+    let mut tokens = Vec::new();
+    for lexeme in lexemes {
+        match lexeme.kind {
+            LexKind::Word | LexKind::ConditionalOperator => {
+                tokens.push(Token::Word(input[lexeme.range].to_string()))
+            }
+            LexKind::Operator(token) => tokens.push(token),
+            // A newline ends a command unless the line already ended with an operator.
+            LexKind::Newline => {
+                if !matches!(
+                    tokens.last(),
+                    None | Some(Token::And | Token::Or | Token::Pipe | Token::Semi)
+                ) {
+                    tokens.push(Token::Semi);
                 }
             }
+            LexKind::Quote => {}
         }
     }
+    Ok(tokens)
+}
 
-    if escaped {
-        return Err("Incomplete".to_string());
+// Classifies the lexer's output for highlighting. Unlike parse_tokens it never fails, so
+// partial input still gets spans; quoted spans follow the word span that contains them.
+pub fn token_spans(input: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut command_seen = false;
+    let mut after_redirect = false;
+
+    for lexeme in lex(input).0 {
+        let kind = match lexeme.kind {
+            LexKind::Word => {
+                let text = &input[lexeme.range.clone()];
+                let kind = if after_redirect {
+                    SpanKind::Target
+                } else if command_seen || split_assignment(text).is_some() {
+                    SpanKind::Argument
+                } else {
+                    SpanKind::Command
+                };
+                after_redirect = false;
+                command_seen |= kind == SpanKind::Command;
+                kind
+            }
+            LexKind::Quote => SpanKind::Quoted,
+            LexKind::ConditionalOperator => SpanKind::Operator,
+            LexKind::Operator(Token::Redirect(_)) => {
+                after_redirect = true;
+                SpanKind::Operator
+            }
+            LexKind::Operator(_) => {
+                command_seen = false;
+                after_redirect = false;
+                SpanKind::Operator
+            }
+            LexKind::Newline => {
+                command_seen = false;
+                after_redirect = false;
+                continue;
+            }
+        };
+        spans.push(Span {
+            kind,
+            range: lexeme.range,
+        });
     }
+    spans
+}
 
-    if mode != Mode::Normal {
-        return Err("Incomplete".to_string());
+fn expand_aliases(
+    tokens: Vec<Token>,
+    aliases: &Aliases,
+    expanding: &mut Vec<String>,
+) -> Vec<Token> {
+    let mut out = Vec::new();
+    let mut command_start = true;
+    let mut redirect_target = false;
+
+    for token in tokens {
+        match &token {
+            Token::Word(_) if redirect_target => redirect_target = false,
+            Token::Word(word) if command_start => {
+                command_start = false;
+                if let Some(value) = aliases.get(word)
+                    && !expanding.contains(word)
+                    && let Ok(alias_tokens) = parse_tokens(value)
+                {
+                    expanding.push(word.clone());
+                    out.extend(expand_aliases(alias_tokens, aliases, expanding));
+                    expanding.pop();
+                    command_start = value.ends_with(char::is_whitespace)
+                        || matches!(
+                            out.last(),
+                            Some(Token::And | Token::Or | Token::Pipe | Token::Semi)
+                        );
+                    continue;
+                }
+            }
+            Token::Word(_) => {}
+            Token::Redirect(_) => redirect_target = true,
+            _ => command_start = true,
+        }
+        out.push(token);
     }
+    out
+}
+
+fn unexpected(token: &str) -> ParseResult {
+    ParseResult::Error(format!("syntax error near unexpected token `{}'", token))
+}
 
-    if !current_token.is_empty() {
-        current_args.push(current_token);
+fn parse_list(tokens: Vec<Token>) -> ParseResult {
+    let mut list = Vec::new();
+    let mut connector = Connector::Seq;
+    let mut commands: Vec<SimpleCommand> = Vec::new();
+    let mut current = SimpleCommand::default();
+    let mut tokens = tokens.into_iter();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => current.words.push(word),
+            Token::Redirect(kind) => match tokens.next() {
+                Some(Token::Word(target)) => current.redirects.push(Redirect { kind, target }),
+                Some(other) => return unexpected(other.text()),
+                None => return unexpected("newline"),
+            },
+            Token::Pipe => {
+                if current.is_empty() {
+                    return unexpected(token.text());
+                }
+                commands.push(std::mem::take(&mut current));
+            }
+            Token::And | Token::Or | Token::Semi => {
+                if current.is_empty() {
+                    return unexpected(token.text());
+                }
+                commands.push(std::mem::take(&mut current));
+                list.push(Pipeline {
+                    connector,
+                    commands: std::mem::take(&mut commands),
+                });
+                connector = match token {
+                    Token::And => Connector::And,
+                    Token::Or => Connector::Or,
+                    _ => Connector::Seq,
+                };
+            }
+        }
     }
-    if !current_args.is_empty() {
-        commands.push(current_args);
+
+    if current.is_empty() {
+        if !commands.is_empty() || connector != Connector::Seq {
+            return ParseResult::Incomplete;
+        }
+    } else {
+        commands.push(current);
+        list.push(Pipeline {
+            connector,
+            commands,
+        });
     }
 
-    Ok(commands)
+    ParseResult::Ok(list)
 }
 
-pub fn parse_input(input: &str) -> ParseResult {
+pub fn parse_input(input: &str, aliases: &Aliases) -> ParseResult {
     let trimmed = input.trim();
     if trimmed.is_empty() {
         return ParseResult::Ok(vec![]);
     }
 
     match parse_tokens(trimmed) {
-        Ok(tokenized_commands) => {
-            let mut cmds = Vec::new();
+        Ok(tokens) => parse_list(expand_aliases(tokens, aliases, &mut Vec::new())),
+        Err(_) => ParseResult::Incomplete,
+    }
+}
 
-            for args in tokenized_commands {
-                if args.is_empty() {
-                    continue;
-                }
+// The last word of a command line as `!$` recalls it, skipping trailing operators.
+pub fn last_word(input: &str) -> Option<String> {
+    match parse_tokens(input) {
+        Ok(tokens) => tokens.into_iter().rev().find_map(|token| match token {
+            Token::Word(word) => Some(word),
+            _ => None,
+        }),
+        Err(_) => input.split_whitespace().last().map(str::to_string),
+    }
+}
 
-                let cmd = args[0].as_str();
-                let mut cmd_args = args[1..].to_vec();
-                let uses_args = cmd_args.clone();
-                cmd_args = cmd_args
-                    .iter()
-                    .map(|ele| ele.replace("\n", "\\n"))
-                    .collect();
-
-                let parsed = match cmd {
-                    "ls" => CommandEnum::Ls(cmd_args),
-                    "cat" => CommandEnum::Cat(cmd_args),
-                    "cp" => CommandEnum::Cp(cmd_args),
-                    "pwd" => CommandEnum::Pwd,
-                    "cd" => CommandEnum::Cd(cmd_args, uses_args),
-                    "echo" => CommandEnum::Echo(uses_args),
-                    "rm" => CommandEnum::Rm(cmd_args),
-                    "mkdir" => CommandEnum::Mkdir(uses_args, cmd_args),
-                    "mv" => CommandEnum::Mv(cmd_args),
-                    "exit" => CommandEnum::Exit,
-                    "clear" => {
-                        clear();
-                        continue;
-                    }
-                    _ => CommandEnum::Unknown(args[0].clone()),
-                };
-                cmds.push(parsed);
-            }
-            ParseResult::Ok(cmds)
-        }
-        Err(_) => ParseResult::Incomplete,
+pub fn build_command(args: Vec<String>) -> CommandEnum {
+    let cmd = args[0].as_str();
+    let uses_args = args[1..].to_vec();
+    let cmd_args: Vec<String> = uses_args
+        .iter()
+        .map(|ele| ele.replace("\n", "\\n"))
+        .collect();
+
+    match cmd {
+        "ls" => CommandEnum::Ls(cmd_args),
+        "cat" => CommandEnum::Cat(cmd_args),
+        "cp" => CommandEnum::Cp(cmd_args),
+        "pwd" => CommandEnum::Pwd(uses_args),
+        "cd" => CommandEnum::Cd(cmd_args, uses_args),
+        "echo" => CommandEnum::Echo(uses_args),
+        "rm" => CommandEnum::Rm(cmd_args),
+        "mkdir" => CommandEnum::Mkdir(uses_args, cmd_args),
+        "mv" => CommandEnum::Mv(cmd_args),
+        "exit" => CommandEnum::Exit,
+        "set" => CommandEnum::Set(uses_args),
+        "test" => CommandEnum::Test(uses_args, false),
+        "[" => CommandEnum::Test(uses_args, true),
+        "read" => CommandEnum::Read(uses_args),
+        "printf" => CommandEnum::Printf(uses_args),
+        "type" => CommandEnum::Type(uses_args),
+        "which" => CommandEnum::Which(uses_args),
+        "command" => CommandEnum::Command(uses_args),
+        "hash" => CommandEnum::Hash(uses_args),
+        "alias" => CommandEnum::Alias(uses_args),
+        "unalias" => CommandEnum::Unalias(uses_args),
+        "history" => CommandEnum::History(uses_args),
+        "complete" => CommandEnum::Complete(uses_args),
+        "compgen" => CommandEnum::Compgen(uses_args),
+        "pushd" => CommandEnum::Pushd(uses_args),
+        "popd" => CommandEnum::Popd(uses_args),
+        "dirs" => CommandEnum::Dirs(uses_args),
+        "z" | "j" => CommandEnum::Z(uses_args),
+        "clear" => CommandEnum::Clear,
+        _ => CommandEnum::Unknown(args),
     }
 }
 
-pub fn execute_all(cmds: Vec<CommandEnum>, pwd_state: &mut PwdState) {
-    for cmd in cmds {
-        let keep_running = execute(cmd, pwd_state);
-        if !keep_running {
-            return;
+pub fn execute_all(list: Vec<Pipeline>, state: &mut ShellState) {
+    let mut status = state.last_status;
+
+    for (i, pipeline) in list.iter().enumerate() {
+        let skip = match pipeline.connector {
+            Connector::Seq => false,
+            Connector::And => status != 0,
+            Connector::Or => status == 0,
+        };
+        if skip {
+            continue;
+        }
+
+        status = match run_pipeline(pipeline, state) {
+            Ok(code) => code,
+            Err(msg) => {
+                eprintln!("0-shell: {}", msg);
+                state.last_status = 1;
+                return;
+            }
+        };
+        state.last_status = status;
+
+        let in_and_or_list = list
+            .get(i + 1)
+            .is_some_and(|next| next.connector != Connector::Seq);
+        if status != 0 && !in_and_or_list && state.options.is_set(ShellOption::Errexit) {
+            std::process::exit(status);
         }
     }
 }
@@ -191,3 +574,56 @@ pub fn execute_all(cmds: Vec<CommandEnum>, pwd_state: &mut PwdState) {
 pub fn clear() {
     print!("\x1Bc");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &[&str]) -> Vec<Token> {
+        text.iter().map(|w| Token::Word(w.to_string())).collect()
+    }
+
+    #[test]
+    fn closing_brackets_end_the_conditional_before_an_operator() {
+        let mut expected = words(&["[[", "-n", "x", "]]"]);
+        expected.push(Token::And);
+        expected.extend(words(&["echo", "y"]));
+        assert_eq!(parse_tokens("[[ -n x ]]&&echo y"), Ok(expected));
+
+        let mut expected = words(&["[[", "a", "]]"]);
+        expected.push(Token::Or);
+        expected.extend(words(&["b"]));
+        assert_eq!(parse_tokens("[[ a ]]||b"), Ok(expected));
+    }
+
+    #[test]
+    fn operators_inside_the_conditional_stay_words() {
+        assert_eq!(
+            parse_tokens("[[ a && b || c > d ]]"),
+            Ok(words(&["[[", "a", "&&", "b", "||", "c", ">", "d", "]]"]))
+        );
+        assert!(parse_tokens("[[ a &&").is_err());
+    }
+
+    #[test]
+    fn spans_start_a_new_command_after_the_conditional() {
+        let kinds: Vec<SpanKind> = token_spans("[[ 'a' > b ]]&&echo y")
+            .into_iter()
+            .map(|span| span.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                SpanKind::Command,
+                SpanKind::Argument,
+                SpanKind::Quoted,
+                SpanKind::Operator,
+                SpanKind::Argument,
+                SpanKind::Argument,
+                SpanKind::Operator,
+                SpanKind::Command,
+                SpanKind::Argument,
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use crate::commands::shell_state::ShellState;
+use crate::helpers::executor::command_output;
+use crate::helpers::expand::expand_parameter;
+use chrono::Local;
+use std::{env, iter::Peekable, path::Path, str::Chars, time::Duration};
+
+const NEON_BLUE: &str = "\x1b[38;2;0;180;255m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+const DURATION_THRESHOLD: Duration = Duration::from_secs(3);
+
+pub fn primary_prompt(state: &mut ShellState) -> String {
+    match env::var("PS1") {
+        Ok(template) => expand_prompt(&template, state),
+        Err(_) => format!("{NEON_BLUE}{}$ {RESET}", current_dir(state)),
+    }
+}
+
+pub fn continuation_prompt(state: &mut ShellState) -> String {
+    match env::var("PS2") {
+        Ok(template) => expand_prompt(&template, state),
+        Err(_) => "> ".to_string(),
+    }
+}
+
+// By default the right prompt reports how the previous command went: its exit status
+// when it failed, and how long it took when that was noticeably long.
+pub fn right_prompt(state: &mut ShellState) -> String {
+    if let Ok(template) = env::var("RPROMPT") {
+        return expand_prompt(&template, state);
+    }
+    let mut segments = Vec::new();
+    if state.last_status != 0 {
+        segments.push(format!("{RED}✘ {}{RESET}", state.last_status));
+    }
+    if state.last_duration >= DURATION_THRESHOLD {
+        segments.push(format!(
+            "{YELLOW}{}{RESET}",
+            format_duration(state.last_duration)
+        ));
+    }
+    segments.join(" ")
+}
+
+// Expands bash-style backslash escapes, parameters and command substitutions in a single
+// pass, so text produced by one expansion is never expanded again. `\[` and `\]` become
+// the \x01 / \x02 markers the line editor uses to skip non-printing text, and `\g` adds
+// the git branch of the current directory.
+pub fn expand_prompt(template: &str, state: &mut ShellState) -> String {
+    let mut prompt = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('u') => prompt.push_str(&user()),
+                Some('h') => prompt.push_str(hostname().split('.').next().unwrap_or("")),
+                Some('H') => prompt.push_str(&hostname()),
+                Some('w') => prompt.push_str(&abbreviate_home(&current_dir(state))),
+                Some('W') => prompt.push_str(&base_dir(&current_dir(state))),
+                Some('g') => {
+                    let dir = state.pwd.get_current_dir();
+                    prompt.push_str(&state.git.segment(Path::new(&dir)));
+                }
+                Some('$') if users::get_current_uid() == 0 => prompt.push('#'),
+                Some('$') => prompt.push('$'),
+                Some('t') => prompt.push_str(&Local::now().format("%H:%M:%S").to_string()),
+                Some('T') => prompt.push_str(&Local::now().format("%I:%M:%S").to_string()),
+                Some('A') => prompt.push_str(&Local::now().format("%H:%M").to_string()),
+                Some('@') => prompt.push_str(&Local::now().format("%I:%M %p").to_string()),
+                Some('d') => prompt.push_str(&Local::now().format("%a %b %d").to_string()),
+                Some('j') => prompt.push('0'),
+                Some('s') => prompt.push_str("0-shell"),
+                Some('n') => prompt.push('\n'),
+                Some('r') => prompt.push('\r'),
+                Some('a') => prompt.push('\x07'),
+                Some('e') => prompt.push('\x1b'),
+                Some('[') => prompt.push('\x01'),
+                Some(']') => prompt.push('\x02'),
+                Some('\\') => prompt.push('\\'),
+                Some(d @ '0'..='7') => prompt.push(octal(d, &mut chars)),
+                Some(other) => {
+                    prompt.push('\\');
+                    prompt.push(other);
+                }
+                None => prompt.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'(') => {
+                chars.next();
+                let command = take_command(&mut chars);
+                prompt.push_str(&command_output(&command, state));
+            }
+            '`' => {
+                let command = take_backquoted(&mut chars);
+                prompt.push_str(&command_output(&command, state));
+            }
+            '$' => match expand_parameter(&mut chars, state) {
+                Ok(Some(value)) => prompt.push_str(&value),
+                Ok(None) => prompt.push('$'),
+                Err(_) => {}
+            },
+            _ => prompt.push(c),
+        }
+    }
+    prompt
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{:.1}s", duration.as_secs_f64()),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+fn current_dir(state: &ShellState) -> String {
+    state.pwd.get_current_dir().replace('\n', "\\n")
+}
+
+pub fn abbreviate_home(dir: &str) -> String {
+    let Ok(home) = env::var("HOME") else {
+        return dir.to_string();
+    };
+    let home = home.trim_end_matches('/');
+    if home.is_empty() {
+        return dir.to_string();
+    }
+    match dir.strip_prefix(home) {
+        Some("") => "~".to_string(),
+        Some(rest) if rest.starts_with('/') => format!("~{}", rest),
+        _ => dir.to_string(),
+    }
+}
+
+fn base_dir(dir: &str) -> String {
+    let abbreviated = abbreviate_home(dir);
+    if abbreviated == "~" || dir == "/" {
+        return abbreviated;
+    }
+    dir.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(dir)
+        .to_string()
+}
+
+fn user() -> String {
+    users::get_current_username()
+        .map(|name| name.to_string_lossy().into_owned())
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is writable for its whole length, which is what we pass.
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return String::new();
+    }
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+fn octal(first: char, chars: &mut Peekable<Chars>) -> char {
+    let mut value = first.to_digit(8).unwrap_or(0);
+    for _ in 0..2 {
+        match chars.peek().and_then(|c| c.to_digit(8)) {
+            Some(digit) => {
+                value = value * 8 + digit;
+                chars.next();
+            }
+            None => break,
+        }
+    }
+    char::from_u32(value).unwrap_or('?')
+}
+
+// Reads up to the parenthesis closing a `$(`, skipping over quoted text.
+fn take_command(chars: &mut Peekable<Chars>) -> String {
+    let mut command = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+    for c in chars.by_ref() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth == 0 => break,
+            (None, ')') => depth -= 1,
+            _ => {}
+        }
+        command.push(c);
+    }
+    command
+}
+
+// Reads up to the closing backquote; `\\`, `\`` and `\$` lose their backslash.
+fn take_backquoted(chars: &mut Peekable<Chars>) -> String {
+    let mut command = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => break,
+            '\\' => match chars.next() {
+                Some(next @ ('\\' | '`' | '$')) => command.push(next),
+                Some(next) => {
+                    command.push('\\');
+                    command.push(next);
+                }
+                None => command.push('\\'),
+            },
+            _ => command.push(c),
+        }
+    }
+    command
+}
@@ -0,0 +1,403 @@
+use crate::helpers::line_edit::{insert_at, remove_range};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViMode {
+    Insert,
+    Normal,
+}
+
+impl ViMode {
+    pub fn indicator(self) -> &'static str {
+        match self {
+            ViMode::Insert => "(ins) ",
+            ViMode::Normal => "(cmd) ",
+        }
+    }
+}
+
+pub enum ViAction {
+    None,
+    Edit(String, usize),
+    HistoryPrev,
+    HistoryNext,
+}
+
+const MAX_COUNT: usize = 9999;
+
+enum Parsed {
+    Incomplete,
+    Invalid,
+    Command {
+        count: usize,
+        operator: Option<char>,
+        key: char,
+        arg: Option<char>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct ViState {
+    pub mode: ViMode,
+    pending: String,
+    register: String,
+    undo: Vec<(String, usize)>,
+    last_change: String,
+    recording: Option<String>,
+}
+
+impl Default for ViState {
+    fn default() -> Self {
+        Self {
+            mode: ViMode::Insert,
+            pending: String::new(),
+            register: String::new(),
+            undo: Vec::new(),
+            last_change: String::new(),
+            recording: None,
+        }
+    }
+}
+
+impl ViState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self {
+            register: std::mem::take(&mut self.register),
+            ..Self::default()
+        };
+    }
+
+    pub fn record(&mut self, c: char) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(c);
+        }
+    }
+
+    pub fn escape(&mut self, line: &str, cursor: usize) -> (String, usize) {
+        self.mode = ViMode::Normal;
+        self.pending.clear();
+        if let Some(mut recording) = self.recording.take() {
+            recording.push('\x1b');
+            self.last_change = recording;
+        }
+        (line.to_string(), cursor.saturating_sub(1))
+    }
+
+    pub fn normal_key(&mut self, c: char, line: &str, cursor: usize) -> ViAction {
+        self.pending.push(c);
+        let (count, operator, key, arg) = match parse(&self.pending) {
+            Parsed::Incomplete => return ViAction::None,
+            Parsed::Invalid => {
+                self.pending.clear();
+                return ViAction::None;
+            }
+            Parsed::Command {
+                count,
+                operator,
+                key,
+                arg,
+            } => (count, operator, key, arg),
+        };
+        let keys = std::mem::take(&mut self.pending);
+
+        let len = line.chars().count();
+        let cursor = cursor.min(len.saturating_sub(1));
+
+        match key {
+            'k' if operator.is_none() => return ViAction::HistoryPrev,
+            'j' if operator.is_none() => return ViAction::HistoryNext,
+            'u' if operator.is_none() => {
+                return match self.undo.pop() {
+                    Some((line, cursor)) => ViAction::Edit(line, cursor),
+                    None => ViAction::None,
+                };
+            }
+            '.' if operator.is_none() => return self.repeat(line, cursor),
+            _ => {}
+        }
+
+        let is_change = operator.is_some_and(|op| op != 'y')
+            || matches!(
+                key,
+                'x' | 'X' | 'p' | 'P' | 'r' | 's' | 'S' | 'C' | 'D' | 'i' | 'a' | 'I' | 'A'
+            );
+        if is_change {
+            self.undo.push((line.to_string(), cursor));
+            self.last_change = keys.clone();
+        }
+
+        let (line, cursor) = match (operator, key) {
+            (None, 'i') => self.insert(line, cursor, keys),
+            (None, 'a') => self.insert(line, (cursor + 1).min(len), keys),
+            (None, 'I') => self.insert(line, 0, keys),
+            (None, 'A') => self.insert(line, len, keys),
+            (None, 'x') => self.apply('d', line, cursor, (cursor + count).min(len), keys),
+            (None, 'X') => self.apply('d', line, cursor.saturating_sub(count), cursor, keys),
+            (None, 's') => self.apply('c', line, cursor, (cursor + count).min(len), keys),
+            (None, 'S') => self.apply('c', line, 0, len, keys),
+            (None, 'C') => self.apply('c', line, cursor, len, keys),
+            (None, 'D') => self.apply('d', line, cursor, len, keys),
+            (None, 'p' | 'P') if self.register.is_empty() => return ViAction::None,
+            (None, 'p' | 'P') => {
+                let text = self.register.repeat(count);
+                let at = if key == 'p' && len > 0 {
+                    cursor + 1
+                } else {
+                    cursor
+                };
+                let end = at + text.chars().count();
+                (insert_at(line, at, &text), end.saturating_sub(1))
+            }
+            (None, 'r') => {
+                let Some(replacement) = arg.filter(|_| cursor + count <= len) else {
+                    return ViAction::None;
+                };
+                let (rest, _) = remove_range(line, cursor, cursor + count);
+                let text: String = std::iter::repeat_n(replacement, count).collect();
+                (insert_at(&rest, cursor, &text), cursor + count - 1)
+            }
+            (Some(op), k) if k == op => self.apply(op, line, 0, len, keys),
+            (operator, key) => {
+                let key = if operator == Some('c') && key == 'w' {
+                    'e'
+                } else {
+                    key
+                };
+                let Some((target, inclusive)) = motion(line, cursor, key, arg, count) else {
+                    return ViAction::None;
+                };
+                match operator {
+                    None => (line.to_string(), target.min(len.saturating_sub(1))),
+                    Some(op) => {
+                        let (start, end) = if target < cursor {
+                            (target, cursor)
+                        } else {
+                            (cursor, target + usize::from(inclusive))
+                        };
+                        self.apply(op, line, start, end.min(len), keys)
+                    }
+                }
+            }
+        };
+        ViAction::Edit(line, cursor)
+    }
+
+    fn insert(&mut self, line: &str, cursor: usize, keys: String) -> (String, usize) {
+        self.mode = ViMode::Insert;
+        self.recording = Some(keys);
+        (line.to_string(), cursor)
+    }
+
+    fn apply(
+        &mut self,
+        operator: char,
+        line: &str,
+        start: usize,
+        end: usize,
+        keys: String,
+    ) -> (String, usize) {
+        let (rest, removed) = remove_range(line, start, end);
+        self.register = removed;
+        match operator {
+            'y' => (line.to_string(), start),
+            'c' => self.insert(&rest, start, keys),
+            _ => {
+                let max = rest.chars().count().saturating_sub(1);
+                (rest, start.min(max))
+            }
+        }
+    }
+
+    fn repeat(&mut self, line: &str, cursor: usize) -> ViAction {
+        let keys = self.last_change.clone();
+        if keys.is_empty() {
+            return ViAction::None;
+        }
+
+        let undo_len = self.undo.len();
+        let mut line = line.to_string();
+        let mut cursor = cursor;
+        for c in keys.chars() {
+            match (self.mode, c) {
+                (ViMode::Normal, c) => {
+                    if let ViAction::Edit(l, pos) = self.normal_key(c, &line, cursor) {
+                        line = l;
+                        cursor = pos;
+                    }
+                }
+                (ViMode::Insert, '\x1b') => {
+                    self.recording = None;
+                    self.mode = ViMode::Normal;
+                    cursor = cursor.saturating_sub(1);
+                }
+                (ViMode::Insert, '\x08') => {
+                    if cursor > 0 {
+                        line = remove_range(&line, cursor - 1, cursor).0;
+                        cursor -= 1;
+                    }
+                }
+                (ViMode::Insert, c) => {
+                    line = insert_at(&line, cursor, c.encode_utf8(&mut [0; 4]));
+                    cursor += 1;
+                }
+            }
+        }
+        self.undo.truncate(undo_len + 1);
+        self.last_change = keys;
+        ViAction::Edit(line, cursor)
+    }
+}
+
+fn parse(keys: &str) -> Parsed {
+    let mut chars = keys.chars().peekable();
+    let mut count = read_count(&mut chars);
+
+    let mut operator = None;
+    if let Some(&c) = chars.peek()
+        && matches!(c, 'd' | 'c' | 'y')
+    {
+        operator = Some(c);
+        chars.next();
+        let inner = read_count(&mut chars);
+        count = (count.max(1) * inner.max(1)).min(MAX_COUNT);
+    }
+
+    let Some(key) = chars.next() else {
+        return Parsed::Incomplete;
+    };
+    let needs_arg = matches!(key, 'f' | 't' | 'F' | 'T' | 'r');
+    let arg = if needs_arg {
+        match chars.next() {
+            Some(arg) => Some(arg),
+            None => return Parsed::Incomplete,
+        }
+    } else {
+        None
+    };
+
+    let valid = match operator {
+        Some(op) => key == op || "hlwbe0^$fFtT".contains(key),
+        None => "hlwbe0^$fFtTiaIAxXsSCDpPrkju.".contains(key),
+    };
+    if !valid || chars.next().is_some() {
+        return Parsed::Invalid;
+    }
+    Parsed::Command {
+        count: count.max(1),
+        operator,
+        key,
+        arg,
+    }
+}
+
+// Counts stop growing at MAX_COUNT, so a mistyped run of digits can't overflow or
+// paste the register billions of times.
+fn read_count(chars: &mut std::iter::Peekable<std::str::Chars>) -> usize {
+    let mut count = 0;
+    while let Some(&c) = chars.peek() {
+        match c.to_digit(10) {
+            Some(0) if count == 0 => break,
+            Some(d) => count = (count * 10 + d as usize).min(MAX_COUNT),
+            None => break,
+        }
+        chars.next();
+    }
+    count
+}
+
+fn class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+// Returns the target position and whether an operator should include it.
+fn motion(
+    line: &str,
+    cursor: usize,
+    key: char,
+    arg: Option<char>,
+    count: usize,
+) -> Option<(usize, bool)> {
+    let chars: Vec<char> = line.chars().collect();
+    let len = chars.len();
+    let count = count.min(len.max(1));
+    let mut pos = cursor;
+
+    match key {
+        'h' => Some((cursor.saturating_sub(count), false)),
+        'l' => Some(((cursor + count).min(len), false)),
+        '0' => Some((0, false)),
+        '^' => Some((
+            chars.iter().take_while(|c| c.is_whitespace()).count(),
+            false,
+        )),
+        '$' => Some((len.saturating_sub(1), true)),
+        'w' => {
+            for _ in 0..count {
+                if pos < len {
+                    let start_class = class(chars[pos]);
+                    while pos < len && start_class != 0 && class(chars[pos]) == start_class {
+                        pos += 1;
+                    }
+                }
+                while pos < len && class(chars[pos]) == 0 {
+                    pos += 1;
+                }
+            }
+            Some((pos, false))
+        }
+        'b' => {
+            for _ in 0..count {
+                while pos > 0 && class(chars[pos - 1]) == 0 {
+                    pos -= 1;
+                }
+                if pos > 0 {
+                    let word_class = class(chars[pos - 1]);
+                    while pos > 0 && class(chars[pos - 1]) == word_class {
+                        pos -= 1;
+                    }
+                }
+            }
+            Some((pos, false))
+        }
+        'e' => {
+            for _ in 0..count {
+                pos += 1;
+                while pos < len && class(chars[pos]) == 0 {
+                    pos += 1;
+                }
+                if pos >= len {
+                    break;
+                }
+                let word_class = class(chars[pos]);
+                while pos + 1 < len && class(chars[pos + 1]) == word_class {
+                    pos += 1;
+                }
+            }
+            Some((pos.min(len.saturating_sub(1)), true))
+        }
+        'f' | 't' => {
+            let target = arg?;
+            for _ in 0..count {
+                pos = (pos + 1..len).find(|&i| chars[i] == target)?;
+            }
+            Some((if key == 't' { pos - 1 } else { pos }, true))
+        }
+        'F' | 'T' => {
+            let target = arg?;
+            for _ in 0..count {
+                pos = (0..pos).rev().find(|&i| chars[i] == target)?;
+            }
+            Some((if key == 'T' { pos + 1 } else { pos }, false))
+        }
+        _ => None,
+    }
+}